@@ -1,4 +1,5 @@
 use clap::Parser;
+use drcov::NoSymbolResolver;
 use std::path::PathBuf;
 use std::process;
 
@@ -37,22 +38,29 @@ fn main() {
     println!("File: {}", args.file.display());
     println!("Version: {}", coverage_data.header.version);
     println!("Flavor: {}", coverage_data.header.flavor);
-    println!(
-        "Module Table Version: {}",
-        coverage_data.module_version as u32
-    );
+    if let Some(metadata) = &coverage_data.header.flavor_metadata {
+        println!("Flavor metadata: {metadata}");
+    }
+    println!("Module Table Version: {}", coverage_data.module_version);
     println!();
 
     println!("=== Summary ===");
-    println!("Total Modules: {}", coverage_data.modules.len());
-    println!("Total Basic Blocks: {}", coverage_data.basic_blocks.len());
+    println!("Total Modules: {}", coverage_data.module_count());
+    println!(
+        "Covered Modules: {} of {}",
+        coverage_data.covered_module_count(),
+        coverage_data.module_count()
+    );
+    println!("Total Basic Blocks: {}", coverage_data.block_count());
 
-    let total_coverage_bytes: u64 = coverage_data
-        .basic_blocks
-        .iter()
-        .map(|bb| bb.size as u64)
-        .sum();
-    println!("Total Coverage: {total_coverage_bytes} bytes");
+    println!(
+        "Total Coverage: {} bytes",
+        coverage_data.total_covered_bytes()
+    );
+    println!(
+        "Unique Coverage: {} bytes",
+        coverage_data.unique_covered_bytes()
+    );
     println!();
 
     println!("=== Module Coverage ===");
@@ -62,23 +70,15 @@ fn main() {
     );
     println!("{}", "-".repeat(80));
 
-    let stats = coverage_data.get_coverage_stats();
-    for module in &coverage_data.modules {
-        let block_count = stats.get(&(module.id as u16)).copied().unwrap_or(0);
-        let module_bytes: u64 = coverage_data
-            .basic_blocks
-            .iter()
-            .filter(|bb| bb.module_id as u32 == module.id)
-            .map(|bb| bb.size as u64)
-            .sum();
-
+    let summaries = coverage_data.module_summaries();
+    for (module, summary) in coverage_data.modules.iter().zip(&summaries) {
         println!(
             "{:<4} {:<8} {:<12} 0x{:016x} {}",
-            module.id,
-            block_count,
-            format!("{} bytes", module_bytes),
+            summary.id,
+            summary.block_count,
+            format!("{} bytes", summary.covered_bytes),
             module.base,
-            module.path
+            summary.path
         );
     }
     println!();
@@ -91,12 +91,8 @@ fn main() {
         );
         println!("{}", "-".repeat(80));
         for bb in &coverage_data.basic_blocks {
-            if let Some(module) = coverage_data.find_module(bb.module_id) {
-                let abs_addr = bb.absolute_address(module);
-                println!(
-                    "{:<8} 0x{:<11x} {:<8} 0x{:<15x} {}",
-                    bb.module_id, bb.start, bb.size, abs_addr, module.path
-                );
+            if let Some(row) = coverage_data.format_block(bb, &NoSymbolResolver) {
+                println!("{row}");
             }
         }
         println!();
@@ -104,32 +100,23 @@ fn main() {
 
     if let Some(module_filter) = &args.module {
         println!("=== Module-Specific Analysis: {module_filter} ===");
-        let mut found = false;
-        for module in coverage_data
-            .modules
-            .iter()
-            .filter(|m| m.path.contains(module_filter))
-        {
-            found = true;
+        let matches = coverage_data.find_modules_by_path_substring(module_filter);
+        for module in &matches {
             println!("Module ID: {}", module.id);
             println!("Name: {}", module.path);
             println!("Base: 0x{:x}", module.base);
             println!("End: 0x{:x}", module.end);
             println!("Size: {} bytes", module.size());
 
-            let block_count = stats.get(&(module.id as u16)).copied().unwrap_or(0);
-            let module_bytes: u64 = coverage_data
-                .basic_blocks
-                .iter()
-                .filter(|bb| bb.module_id as u32 == module.id)
-                .map(|bb| bb.size as u64)
-                .sum();
-
-            println!("Covered Blocks: {block_count}");
-            println!("Covered Bytes: {module_bytes}");
+            let summary = &summaries[module.id as usize];
+            println!("Covered Blocks: {}", summary.block_count);
+            println!("Covered Bytes: {}", summary.covered_bytes);
+            if let Some(ratio) = summary.coverage_ratio {
+                println!("Coverage: {:.2}%", ratio * 100.0);
+            }
             println!();
         }
-        if !found {
+        if matches.is_empty() {
             println!("No modules found matching: {module_filter}");
         }
     }
@@ -0,0 +1,110 @@
+use clap::Parser;
+use drcov::{BasicBlock, CoverageData, ModuleTableVersion};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "A tool to write DrCov files.", long_about = None)]
+struct Args {
+    /// Path of the .drcov file to write
+    #[arg(required = true)]
+    output: PathBuf,
+
+    /// Tool flavor string
+    #[arg(short, long, default_value = "drcov")]
+    flavor: String,
+
+    /// Module table version (legacy, v2, v3, v4)
+    #[arg(long, default_value = "v4")]
+    module_version: ModuleTableVersion,
+
+    /// Module as `path,base,end` (repeatable, IDs assigned in order)
+    #[arg(short, long = "module", value_name = "PATH,BASE,END")]
+    modules: Vec<String>,
+
+    /// Basic block as `module_id,offset,size` (repeatable)
+    #[arg(short, long = "block", value_name = "MODULE_ID,OFFSET,SIZE")]
+    blocks: Vec<String>,
+
+    /// File with one `module_id,offset,size` block per line
+    #[arg(long, value_name = "FILE")]
+    blocks_file: Option<PathBuf>,
+}
+
+/// Parses a field with [`drcov::parse_number`], checking it fits in `T`.
+fn parse_number<T: TryFrom<u64>>(s: &str) -> Result<T, String> {
+    let s = s.trim();
+    let value = drcov::parse_number(s).ok_or_else(|| format!("invalid number '{s}'"))?;
+    T::try_from(value).map_err(|_| format!("number out of range '{s}'"))
+}
+
+fn parse_module(spec: &str) -> Result<(String, u64, u64), String> {
+    // Split from the right so paths may contain commas
+    let mut parts = spec.rsplitn(3, ',');
+    let (Some(end), Some(base), Some(path)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("invalid module '{spec}', expected PATH,BASE,END"));
+    };
+    Ok((path.to_string(), parse_number(base)?, parse_number(end)?))
+}
+
+fn parse_block(spec: &str) -> Result<BasicBlock, String> {
+    let parts: Vec<_> = spec.split(',').collect();
+    let [module_id, offset, size] = parts[..] else {
+        return Err(format!(
+            "invalid block '{spec}', expected MODULE_ID,OFFSET,SIZE"
+        ));
+    };
+    Ok(BasicBlock {
+        module_id: parse_number(module_id)?,
+        start: parse_number(offset)?,
+        size: parse_number(size)?,
+    })
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let mut builder = CoverageData::builder()
+        .flavor(&args.flavor)
+        .module_version(args.module_version);
+
+    for spec in &args.modules {
+        let (path, base, end) = parse_module(spec)?;
+        builder = builder.add_module(&path, base, end);
+    }
+
+    let mut block_specs = args.blocks.clone();
+    if let Some(blocks_file) = &args.blocks_file {
+        let contents = fs::read_to_string(blocks_file)
+            .map_err(|e| format!("failed to read '{}': {e}", blocks_file.display()))?;
+        block_specs.extend(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(str::to_string),
+        );
+    }
+    let blocks = block_specs
+        .iter()
+        .map(|spec| parse_block(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let coverage = builder
+        .add_blocks(blocks)
+        .build()
+        .map_err(|e| e.to_string())?;
+    drcov::to_file(&coverage, &args.output).map_err(|e| e.to_string())
+}
+
+fn main() {
+    let args = Args::parse();
+
+    if let Err(e) = run(&args) {
+        eprintln!(
+            "Error: Failed to write DrCov file '{}': {}",
+            args.output.display(),
+            e
+        );
+        process::exit(1);
+    }
+}
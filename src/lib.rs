@@ -35,11 +35,13 @@
 //! drcov::to_file(&new_coverage, "output.drcov").unwrap();
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{self, Display, Formatter};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read, Write};
-use std::path::Path;
+use std::hash::Hash;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 /// A specialized `Result` type for drcov operations.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -49,6 +51,8 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     /// An I/O error occurred while reading or writing.
     Io(io::Error),
+    /// An I/O error occurred while reading or writing a file.
+    IoWithPath { path: PathBuf, source: io::Error },
     /// The file format is invalid or malformed.
     InvalidFormat(String),
     /// The drcov file version is not supported.
@@ -61,10 +65,59 @@ pub enum Error {
     ValidationError(String),
 }
 
+/// The category of an [`Error`], without its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    Io,
+    InvalidFormat,
+    UnsupportedVersion,
+    InvalidModuleTable,
+    InvalidBbTable,
+    ValidationError,
+}
+
+impl Error {
+    /// Attaches a file path to I/O errors, leaving other errors unchanged.
+    fn with_path(self, path: &Path) -> Self {
+        match self {
+            Error::Io(source) => Error::IoWithPath {
+                path: path.to_path_buf(),
+                source,
+            },
+            other => other,
+        }
+    }
+
+    /// Converts a bincode error, keeping I/O failures as `Error::Io`.
+    #[cfg(feature = "bincode")]
+    fn from_bincode(err: bincode::ErrorKind) -> Self {
+        match err {
+            bincode::ErrorKind::Io(source) => Error::Io(source),
+            other => Error::InvalidFormat(format!("Invalid bincode data: {other}")),
+        }
+    }
+
+    /// Returns the kind of this error, convenient for comparisons.
+    /// Both `Io` and `IoWithPath` are `ErrorKind::Io`.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Io(_) | Error::IoWithPath { .. } => ErrorKind::Io,
+            Error::InvalidFormat(_) => ErrorKind::InvalidFormat,
+            Error::UnsupportedVersion(_) => ErrorKind::UnsupportedVersion,
+            Error::InvalidModuleTable(_) => ErrorKind::InvalidModuleTable,
+            Error::InvalidBbTable(_) => ErrorKind::InvalidBbTable,
+            Error::ValidationError(_) => ErrorKind::ValidationError,
+        }
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Error::Io(e) => write!(f, "I/O error: {e}"),
+            Error::IoWithPath { path, source } => {
+                write!(f, "I/O error on '{}': {source}", path.display())
+            }
             Error::InvalidFormat(msg) => write!(f, "Invalid format: {msg}"),
             Error::UnsupportedVersion(v) => write!(f, "Unsupported drcov version: {v}"),
             Error::InvalidModuleTable(msg) => write!(f, "Invalid module table: {msg}"),
@@ -77,7 +130,7 @@ impl Display for Error {
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            Error::Io(e) => Some(e),
+            Error::Io(e) | Error::IoWithPath { source: e, .. } => Some(e),
             _ => None,
         }
     }
@@ -93,18 +146,30 @@ impl From<io::Error> for Error {
 mod consts {
     pub(crate) const SUPPORTED_FILE_VERSION: u32 = 2;
     pub(crate) const BB_ENTRY_SIZE: usize = 8;
+    pub(crate) const BB_WIDE_ENTRY_SIZE: usize = 16;
+    /// Number of BB records decoded or encoded at a time (64 KiB of binary data).
+    pub(crate) const BB_CHUNK_RECORDS: usize = 8192;
+    pub(crate) const MAX_MODULES: usize = u16::MAX as usize + 1;
+    /// Largest module, in bytes, for which a coverage bitmap is built (64 MiB).
+    pub(crate) const MAX_BITMAP_MODULE_SIZE: u64 = 64 << 20;
     pub(crate) const VERSION_PREFIX: &str = "DRCOV VERSION: ";
     pub(crate) const FLAVOR_PREFIX: &str = "DRCOV FLAVOR: ";
     pub(crate) const MODULE_TABLE_PREFIX: &str = "Module Table: ";
     pub(crate) const BB_TABLE_PREFIX: &str = "BB Table: ";
+    pub(crate) const BB_TABLE_SUFFIX: &str = "bbs";
     pub(crate) const COLUMNS_PREFIX: &str = "Columns: ";
+    pub(crate) const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
 }
 
 /// DrCov file header containing version and tool information.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
 pub struct FileHeader {
     pub version: u32,
     pub flavor: String,
+    /// Text following the first tab on the flavor line, which some tracers
+    /// use for build metadata. Written back after a tab.
+    pub flavor_metadata: Option<String>,
 }
 
 impl Default for FileHeader {
@@ -112,12 +177,14 @@ impl Default for FileHeader {
         Self {
             version: consts::SUPPORTED_FILE_VERSION,
             flavor: "drcov".to_string(),
+            flavor_metadata: None,
         }
     }
 }
 
 /// Module table format versions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
 pub enum ModuleTableVersion {
     #[default]
     Legacy = 1,
@@ -126,34 +193,160 @@ pub enum ModuleTableVersion {
     V4 = 4,
 }
 
+impl Display for ModuleTableVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleTableVersion::Legacy => write!(f, "legacy"),
+            ModuleTableVersion::V2 => write!(f, "v2"),
+            ModuleTableVersion::V3 => write!(f, "v3"),
+            ModuleTableVersion::V4 => write!(f, "v4"),
+        }
+    }
+}
+
+impl FromStr for ModuleTableVersion {
+    type Err = Error;
+
+    /// Parses `legacy`, `2`/`v2`, `3`/`v3` or `4`/`v4` (case-insensitive).
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "legacy" => Ok(ModuleTableVersion::Legacy),
+            "2" | "v2" => Ok(ModuleTableVersion::V2),
+            "3" | "v3" => Ok(ModuleTableVersion::V3),
+            "4" | "v4" => Ok(ModuleTableVersion::V4),
+            _ => Err(Error::InvalidModuleTable(format!(
+                "Unsupported module table version: {s}"
+            ))),
+        }
+    }
+}
+
 /// Represents a loaded module/library in the traced process.
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+///
+/// The default has an entry point of 0 and no other optional fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
 pub struct ModuleEntry {
     pub id: u32,
     pub base: u64,
     pub end: u64,
-    pub entry: u64,
+    /// Entry point address, or `None` if the parsed module table had no
+    /// `entry` column. The writer leaves the column out again only if the
+    /// source lacked it and no module has since been given an entry point;
+    /// otherwise `None` is written as `0x0`.
+    pub entry: Option<u64>,
     pub path: String,
     pub containing_id: Option<i32>,
+    /// File offset of the mapped segment (V4 tables). This is unsigned in
+    /// DynamoRIO; negative values such as `-0x1000` are rejected when parsing
+    /// rather than reinterpreted.
     pub offset: Option<u64>,
     pub checksum: Option<u32>,
     pub timestamp: Option<u32>,
 }
 
+impl Default for ModuleEntry {
+    fn default() -> Self {
+        ModuleEntry {
+            id: 0,
+            base: 0,
+            end: 0,
+            entry: Some(0),
+            path: String::new(),
+            containing_id: None,
+            offset: None,
+            checksum: None,
+            timestamp: None,
+        }
+    }
+}
+
 impl ModuleEntry {
-    /// Returns the size of the module in bytes.
+    /// Creates a `ModuleEntryBuilder` for a module with ID 0 spanning
+    /// `[base, end)`. The entry point starts out as 0 and the other optional
+    /// fields start out unset.
+    pub fn builder(path: &str, base: u64, end: u64) -> ModuleEntryBuilder {
+        ModuleEntryBuilder {
+            entry: ModuleEntry {
+                path: path.to_string(),
+                base,
+                end,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Returns the size of the module in bytes, or 0 if `end` is below
+    /// `base`.
     pub fn size(&self) -> u64 {
         self.end.saturating_sub(self.base)
     }
 
+    /// Returns the size of the module in bytes, or `None` if `end` is below
+    /// `base`.
+    pub fn size_checked(&self) -> Option<u64> {
+        self.end.checked_sub(self.base)
+    }
+
     /// Checks if a given memory address is within this module's range.
     pub fn contains_address(&self, addr: u64) -> bool {
         addr >= self.base && addr < self.end
     }
 }
 
+/// A builder for `ModuleEntry` values, for use with
+/// [`CoverageBuilder::add_full_module`].
+#[derive(Debug, Clone)]
+pub struct ModuleEntryBuilder {
+    entry: ModuleEntry,
+}
+
+impl ModuleEntryBuilder {
+    /// Sets the module ID.
+    pub fn id(mut self, id: u32) -> Self {
+        self.entry.id = id;
+        self
+    }
+
+    /// Sets the entry point address.
+    pub fn entry(mut self, entry: u64) -> Self {
+        self.entry.entry = Some(entry);
+        self
+    }
+
+    /// Sets the ID of the containing module (V3 and later).
+    pub fn containing_id(mut self, containing_id: i32) -> Self {
+        self.entry.containing_id = Some(containing_id);
+        self
+    }
+
+    /// Sets the file offset of the segment (V4).
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.entry.offset = Some(offset);
+        self
+    }
+
+    /// Sets the PE checksum (Windows).
+    pub fn checksum(mut self, checksum: u32) -> Self {
+        self.entry.checksum = Some(checksum);
+        self
+    }
+
+    /// Sets the PE timestamp (Windows).
+    pub fn timestamp(mut self, timestamp: u32) -> Self {
+        self.entry.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Returns the finished `ModuleEntry`.
+    pub fn build(self) -> ModuleEntry {
+        self.entry
+    }
+}
+
 /// Represents an executed basic block.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
 pub struct BasicBlock {
     /// Offset of the basic block start from the image base.
     pub start: u32,
@@ -164,19 +357,252 @@ pub struct BasicBlock {
 }
 
 impl BasicBlock {
+    /// Decodes a basic block from its 8-byte little-endian BB table record
+    /// (`start: u32`, `size: u16`, `module_id: u16`).
+    pub fn from_bytes(bytes: &[u8; 8]) -> BasicBlock {
+        BasicBlock {
+            start: u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            size: u16::from_le_bytes([bytes[4], bytes[5]]),
+            module_id: u16::from_le_bytes([bytes[6], bytes[7]]),
+        }
+    }
+
+    /// Encodes the basic block as its 8-byte little-endian BB table record.
+    pub fn to_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        bytes[0..4].copy_from_slice(&self.start.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.size.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.module_id.to_le_bytes());
+        bytes
+    }
+
     /// Calculates the absolute memory address of the basic block.
     pub fn absolute_address(&self, module: &ModuleEntry) -> u64 {
         module.base + self.start as u64
     }
+
+    /// Calculates the absolute memory address of the basic block, returning
+    /// `None` if it would overflow `u64`.
+    pub fn absolute_address_checked(&self, module: &ModuleEntry) -> Option<u64> {
+        module.base.checked_add(self.start as u64)
+    }
+}
+
+/// A basic block in the 16-byte wide BB record layout used by some custom
+/// tracers, for modules whose offsets do not fit in 32 bits.
+///
+/// Read and written with [`from_reader_wide`] and [`to_writer_wide`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
+pub struct BasicBlockWide {
+    /// Offset of the basic block start from the image base.
+    pub start: u64,
+    /// Size of the basic block in bytes.
+    pub size: u32,
+    /// ID of the module where the basic block is located.
+    pub module_id: u32,
+}
+
+impl BasicBlockWide {
+    /// Decodes a block from its 16-byte little-endian record
+    /// (`start: u64`, `size: u32`, `module_id: u32`).
+    pub fn from_bytes(bytes: &[u8; 16]) -> BasicBlockWide {
+        BasicBlockWide {
+            start: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            size: u32::from_le_bytes(bytes[8..12].try_into().unwrap()),
+            module_id: u32::from_le_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    /// Encodes the block as its 16-byte little-endian record.
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        bytes[0..8].copy_from_slice(&self.start.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.size.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.module_id.to_le_bytes());
+        bytes
+    }
+}
+
+impl From<BasicBlock> for BasicBlockWide {
+    fn from(bb: BasicBlock) -> Self {
+        BasicBlockWide {
+            start: bb.start as u64,
+            size: bb.size as u32,
+            module_id: bb.module_id as u32,
+        }
+    }
+}
+
+/// Binary layout of BB table records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BbRecordLayout {
+    /// The standard drcov layout: 8 bytes per [`BasicBlock`].
+    Standard,
+    /// A non-standard 16-byte layout of [`BasicBlockWide`] records.
+    Wide,
+}
+
+impl BbRecordLayout {
+    fn record_size(self) -> usize {
+        match self {
+            BbRecordLayout::Standard => consts::BB_ENTRY_SIZE,
+            BbRecordLayout::Wide => consts::BB_WIDE_ENTRY_SIZE,
+        }
+    }
+}
+
+/// A basic block paired with the module it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResolvedBlock<'a> {
+    /// The module containing the basic block.
+    pub module: &'a ModuleEntry,
+    /// Offset of the basic block start from the image base.
+    pub offset: u32,
+    /// Size of the basic block in bytes.
+    pub size: u16,
+}
+
+impl ResolvedBlock<'_> {
+    /// Calculates the absolute memory address of the basic block.
+    pub fn absolute_address(&self) -> u64 {
+        self.module.base + self.offset as u64
+    }
+
+    /// Returns the path of the module containing the basic block.
+    pub fn module_path(&self) -> &str {
+        &self.module.path
+    }
+}
+
+/// Per-module coverage totals, as produced by [`CoverageData::module_summaries`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleSummary {
+    pub id: u32,
+    pub path: String,
+    /// Size of the module in bytes.
+    pub size: u64,
+    /// Number of basic blocks in the module.
+    pub block_count: usize,
+    /// Sum of the sizes of the module's basic blocks.
+    pub covered_bytes: u64,
+    /// `covered_bytes / size`, or `None` for empty or inverted modules.
+    pub coverage_ratio: Option<f64>,
+}
+
+/// Dataset-wide totals, as produced by [`CoverageData::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CoverageStats {
+    pub module_count: usize,
+    pub block_count: usize,
+    /// Sum of all block sizes, see [`CoverageData::total_covered_bytes`].
+    pub total_bytes: u64,
+    /// Distinct covered bytes, see [`CoverageData::unique_covered_bytes`].
+    pub unique_bytes: u64,
+    /// Number of modules with at least one block.
+    pub covered_modules: usize,
+    /// Number of modules without any blocks.
+    pub uncovered_modules: usize,
+}
+
+/// Resolves module offsets to symbol names, e.g. via addr2line or a symbol map.
+pub trait SymbolResolver {
+    /// Returns a symbolic name such as `main+0x10` for the given offset, or
+    /// `None` if it cannot be resolved.
+    fn resolve(&self, module_path: &str, offset: u32) -> Option<String>;
+}
+
+impl<F: Fn(&str, u32) -> Option<String>> SymbolResolver for F {
+    fn resolve(&self, module_path: &str, offset: u32) -> Option<String> {
+        self(module_path, offset)
+    }
+}
+
+/// A `SymbolResolver` that never resolves anything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoSymbolResolver;
+
+impl SymbolResolver for NoSymbolResolver {
+    fn resolve(&self, _module_path: &str, _offset: u32) -> Option<String> {
+        None
+    }
+}
+
+/// Block-granular address index over the absolute ranges of a coverage set's
+/// basic blocks.
+///
+/// Ranges are kept sorted by start address and searched with a binary
+/// search, so lookups only scan blocks that start close to the address.
+#[derive(Debug, Clone)]
+pub struct IntervalIndex<'a> {
+    /// `(absolute start, absolute end, block)` sorted by start.
+    ranges: Vec<(u64, u64, &'a BasicBlock)>,
+    /// Length of the longest range, bounding how far back a lookup scans.
+    max_len: u64,
+}
+
+impl<'a> IntervalIndex<'a> {
+    /// Builds the index. Empty blocks, blocks referencing an unknown module
+    /// and blocks whose address would overflow `u64` are left out.
+    pub fn new(data: &'a CoverageData) -> Self {
+        let mut ranges: Vec<_> = data
+            .basic_blocks
+            .iter()
+            .filter(|bb| bb.size > 0)
+            .filter_map(|bb| {
+                let start = data.absolute_address_of(bb)?;
+                let end = start.checked_add(bb.size as u64)?;
+                Some((start, end, bb))
+            })
+            .collect();
+        ranges.sort_by_key(|&(start, end, _)| (start, end));
+        let max_len = ranges
+            .iter()
+            .map(|&(start, end, _)| end - start)
+            .max()
+            .unwrap_or(0);
+        IntervalIndex { ranges, max_len }
+    }
+
+    /// Returns every block whose absolute range contains `addr`, ordered by
+    /// start address.
+    pub fn lookup(&self, addr: u64) -> Vec<&'a BasicBlock> {
+        let lo = addr.saturating_sub(self.max_len);
+        let first = self.ranges.partition_point(|&(start, _, _)| start < lo);
+        let last = self.ranges.partition_point(|&(start, _, _)| start <= addr);
+        self.ranges[first..last]
+            .iter()
+            .filter(|&&(_, end, _)| addr < end)
+            .map(|&(_, _, bb)| bb)
+            .collect()
+    }
+
+    /// Returns the number of indexed blocks.
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    /// Returns true if no blocks were indexed.
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
 }
 
 /// A builder for creating `CoverageData` instances.
 #[derive(Debug, Default)]
 pub struct CoverageBuilder {
     data: CoverageData,
+    reject_inverted_ranges: bool,
+    require_non_empty_paths: bool,
 }
 
 impl CoverageBuilder {
+    /// Creates a builder seeded with an existing module table, e.g. one taken
+    /// from a previous run.
+    pub fn from_modules(modules: Vec<ModuleEntry>) -> CoverageBuilder {
+        CoverageBuilder::default().add_modules(modules)
+    }
+
     /// Sets the tool flavor string.
     pub fn flavor(mut self, flavor: &str) -> Self {
         self.data.header.flavor = flavor.to_string();
@@ -219,29 +645,121 @@ impl CoverageBuilder {
         self
     }
 
+    /// Like `add_coverage`, but fails immediately if no module with
+    /// `module_id` has been added yet instead of waiting for `build()`.
+    pub fn try_add_coverage(self, module_id: u16, offset: u32, size: u16) -> Result<Self> {
+        if module_id as usize >= self.data.modules.len() {
+            return Err(Error::ValidationError(format!(
+                "Basic block references invalid module ID: {module_id}"
+            )));
+        }
+        Ok(self.add_coverage(module_id, offset, size))
+    }
+
     /// Adds a `BasicBlock` struct directly.
     pub fn add_basic_block(mut self, block: BasicBlock) -> Self {
         self.data.basic_blocks.push(block);
         self
     }
 
+    /// Adds many fully-specified module entries at once.
+    /// Module IDs are checked by `build()`.
+    pub fn add_modules<I: IntoIterator<Item = ModuleEntry>>(mut self, modules: I) -> Self {
+        self.data.modules.extend(modules);
+        self
+    }
+
+    /// Adds many basic blocks at once.
+    pub fn add_blocks<I: IntoIterator<Item = BasicBlock>>(mut self, blocks: I) -> Self {
+        self.data.basic_blocks.extend(blocks);
+        self
+    }
+
+    /// Makes `build()` reject modules whose `end` is below their `base`.
+    /// Inverted ranges are accepted by default.
+    pub fn reject_inverted_ranges(mut self) -> Self {
+        self.reject_inverted_ranges = true;
+        self
+    }
+
+    /// Makes `build()` reject modules with an empty path.
+    /// Empty paths are accepted by default.
+    pub fn require_non_empty_paths(mut self) -> Self {
+        self.require_non_empty_paths = true;
+        self
+    }
+
     /// Consumes the builder and returns the final `CoverageData`.
     ///
     /// # Errors
     /// Returns a `ValidationError` if the constructed data is inconsistent.
     pub fn build(self) -> Result<CoverageData> {
         self.data.validate()?;
+        if self.reject_inverted_ranges {
+            if let Some(module) = self.data.modules.iter().find(|m| m.end < m.base) {
+                return Err(Error::ValidationError(format!(
+                    "Module {} has inverted range: end 0x{:x} is below base 0x{:x}",
+                    module.id, module.end, module.base
+                )));
+            }
+        }
+        if self.require_non_empty_paths {
+            if let Some(module) = self.data.modules.iter().find(|m| m.path.is_empty()) {
+                return Err(Error::ValidationError(format!(
+                    "Module {} has an empty path",
+                    module.id
+                )));
+            }
+        }
         Ok(self.data)
     }
 }
 
 /// Complete drcov coverage data structure.
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+///
+/// Equality ignores [`source_columns`](Self::source_columns), which only
+/// records how a parsed file was laid out, so written and re-parsed data
+/// compares equal to the original.
+#[derive(Debug, Clone, Eq, Default)]
+#[cfg_attr(feature = "bincode", derive(serde::Serialize, serde::Deserialize))]
 pub struct CoverageData {
     pub header: FileHeader,
     pub module_version: ModuleTableVersion,
     pub modules: Vec<ModuleEntry>,
     pub basic_blocks: Vec<BasicBlock>,
+    /// Text following the block count in the BB table header, when it is not
+    /// the default `bbs`. An empty string means no suffix.
+    pub bb_table_suffix: Option<String>,
+    /// Columns listed in the module table header of the parsed file, or
+    /// `None` for legacy tables and data not read from a file. Not compared
+    /// by `==`.
+    pub source_columns: Option<Vec<String>>,
+    /// Unrecognized `KEY: value` lines between the flavor line and the module
+    /// table, such as `DRCOV COMMENT: built 2024`, kept when parsing with
+    /// `preserve_extra_headers` and written back verbatim after the flavor.
+    pub extra_headers: Vec<String>,
+}
+
+impl PartialEq for CoverageData {
+    fn eq(&self, other: &Self) -> bool {
+        // Destructured so a new field cannot be silently left out;
+        // `source_columns` is parse provenance, not content.
+        let CoverageData {
+            header,
+            module_version,
+            modules,
+            basic_blocks,
+            bb_table_suffix,
+            source_columns: _,
+            extra_headers,
+        } = self;
+        *header == other.header
+            && *module_version == other.module_version
+            && *modules == other.modules
+            && *basic_blocks == other.basic_blocks
+            && *bb_table_suffix == other.bb_table_suffix
+            && *extra_headers == other.extra_headers
+    }
 }
 
 impl CoverageData {
@@ -250,18 +768,130 @@ impl CoverageData {
         CoverageBuilder::default()
     }
 
+    /// Creates coverage data in which each block is repeated `count` times,
+    /// for consumers that infer hit counts from repeated BB table entries.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if any count is zero or the resulting data
+    /// is inconsistent.
+    pub fn from_hit_counts(
+        modules: Vec<ModuleEntry>,
+        blocks_with_counts: &[(BasicBlock, u32)],
+    ) -> Result<CoverageData> {
+        if let Some((bb, _)) = blocks_with_counts.iter().find(|(_, count)| *count == 0) {
+            return Err(Error::ValidationError(format!(
+                "Hit count for block at offset 0x{:x} in module {} must be nonzero",
+                bb.start, bb.module_id
+            )));
+        }
+        let blocks = blocks_with_counts
+            .iter()
+            .flat_map(|&(bb, count)| std::iter::repeat_n(bb, count as usize));
+        CoverageData::builder()
+            .add_modules(modules)
+            .add_blocks(blocks)
+            .build()
+    }
+
+    /// Creates coverage data from absolute `(address, size)` hits, resolving
+    /// each to a module and offset as
+    /// [`add_absolute_hit`](Self::add_absolute_hit) does.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` if the modules are inconsistent or, unless
+    /// `skip_unresolved` is set, a hit lies outside every module or too far
+    /// past its module base. With `skip_unresolved`, such hits are dropped.
+    pub fn from_absolute_hits(
+        modules: Vec<ModuleEntry>,
+        hits: &[(u64, u16)],
+        flavor: &str,
+        version: ModuleTableVersion,
+        skip_unresolved: bool,
+    ) -> Result<CoverageData> {
+        let mut data = CoverageData::builder()
+            .flavor(flavor)
+            .module_version(version)
+            .add_modules(modules)
+            .build()?;
+        for &(addr, size) in hits {
+            match data.add_absolute_hit(addr, size) {
+                Err(Error::ValidationError(_)) if skip_unresolved => {}
+                result => result?,
+            }
+        }
+        Ok(data)
+    }
+
+    /// Returns `true` if there are neither modules nor basic blocks.
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty() && self.basic_blocks.is_empty()
+    }
+
+    /// Returns the number of modules in the module table.
+    pub fn module_count(&self) -> usize {
+        self.modules.len()
+    }
+
+    /// Returns the number of basic block entries, including repeats.
+    pub fn block_count(&self) -> usize {
+        self.basic_blocks.len()
+    }
+
     /// Validates the integrity of the coverage data.
-    /// Checks for sequential module IDs and valid basic block references.
+    /// Checks for sequential module IDs, valid basic block references, and
+    /// line breaks in the flavor or module paths that would corrupt the file.
     pub fn validate(&self) -> Result<()> {
+        // Block module IDs are u16, so further modules could never be referenced.
+        if self.modules.len() > consts::MAX_MODULES {
+            return Err(Error::ValidationError(format!(
+                "Too many modules: {} exceeds the maximum of {} addressable by basic blocks",
+                self.modules.len(),
+                consts::MAX_MODULES
+            )));
+        }
+
         for (i, module) in self.modules.iter().enumerate() {
             if module.id != i as u32 {
                 return Err(Error::ValidationError(format!(
-                    "Non-sequential module ID {} at index {}",
-                    module.id, i
+                    "module ids must be sequential from 0; found id {} at index {i} (expected {i})",
+                    module.id
                 )));
             }
         }
 
+        let has_line_break = |s: &str| s.contains(['\n', '\r']);
+        if has_line_break(&self.header.flavor)
+            || self
+                .header
+                .flavor_metadata
+                .as_deref()
+                .is_some_and(has_line_break)
+        {
+            return Err(Error::ValidationError(
+                "flavor must not contain line breaks".to_string(),
+            ));
+        }
+        if self.header.flavor.contains('\t') {
+            return Err(Error::ValidationError(
+                "flavor must not contain tabs; use flavor_metadata".to_string(),
+            ));
+        }
+        if let Some(line) = self.extra_headers.iter().find(|line| {
+            has_line_break(line)
+                || !line.contains(':')
+                || line.trim_start().starts_with(consts::MODULE_TABLE_PREFIX)
+        }) {
+            return Err(Error::ValidationError(format!(
+                "extra header line must be a single `KEY: value` line before the module table: {line:?}"
+            )));
+        }
+        if let Some(module) = self.modules.iter().find(|m| has_line_break(&m.path)) {
+            return Err(Error::ValidationError(format!(
+                "path of module {} must not contain line breaks",
+                module.id
+            )));
+        }
+
         let num_modules = self.modules.len();
         for bb in &self.basic_blocks {
             if bb.module_id as usize >= num_modules {
@@ -274,112 +904,1625 @@ impl CoverageData {
         Ok(())
     }
 
-    /// Finds a module by its ID.
-    pub fn find_module(&self, id: u16) -> Option<&ModuleEntry> {
-        self.modules.get(id as usize).filter(|m| m.id == id as u32)
+    /// Panics with the validation error if the data is not valid.
+    ///
+    /// Meant for tests exercising the editing APIs.
+    #[track_caller]
+    pub fn assert_valid(&self) {
+        if let Err(e) = self.validate() {
+            panic!("CoverageData is invalid: {e}");
+        }
     }
 
-    /// Finds a module that contains a given memory address.
-    pub fn find_module_by_address(&self, addr: u64) -> Option<&ModuleEntry> {
-        self.modules.iter().find(|m| m.contains_address(addr))
+    /// Debug-build check that every block references an existing module,
+    /// run after edits that renumber or remove modules.
+    fn debug_check_block_refs(&self) {
+        debug_assert!(
+            self.basic_blocks
+                .iter()
+                .all(|bb| (bb.module_id as usize) < self.modules.len()),
+            "basic block references a module ID outside the module table"
+        );
     }
 
-    /// Calculates coverage statistics, returning a map of module ID to basic block count.
-    pub fn get_coverage_stats(&self) -> HashMap<u16, usize> {
-        let mut stats = HashMap::new();
-        for bb in &self.basic_blocks {
-            *stats.entry(bb.module_id).or_insert(0) += 1;
+    /// Reassigns module IDs sequentially in table order and remaps basic
+    /// blocks to follow their modules.
+    ///
+    /// If several modules share an ID, blocks follow the first of them.
+    /// Blocks referencing an ID that no module has are removed.
+    pub fn reindex_modules(&mut self) {
+        let mut mapping = HashMap::new();
+        for (i, module) in self.modules.iter_mut().enumerate() {
+            mapping.entry(module.id).or_insert(i as u16);
+            module.id = i as u32;
         }
-        stats
+        self.basic_blocks
+            .retain_mut(|bb| match mapping.get(&(bb.module_id as u32)) {
+                Some(&new_id) => {
+                    bb.module_id = new_id;
+                    true
+                }
+                None => false,
+            });
+        self.debug_check_block_refs();
     }
-}
-
-/// Parses a drcov file from a file path.
-pub fn from_file<P: AsRef<Path>>(path: P) -> Result<CoverageData> {
-    from_reader(File::open(path)?)
-}
 
-/// Parses a drcov file from any reader.
-pub fn from_reader<R: Read>(reader: R) -> Result<CoverageData> {
-    let mut reader = BufReader::new(reader);
-    let mut line = String::new();
+    /// Renumbers modules and blocks according to `mapping` (old ID to new
+    /// ID); IDs missing from the mapping are kept. The module table is then
+    /// reordered by the new IDs.
+    ///
+    /// Fails with `Error::ValidationError`, leaving the data untouched, if
+    /// the new IDs collide or are not exactly `0..n`.
+    pub fn remap_module_ids(&mut self, mapping: &HashMap<u16, u16>) -> Result<()> {
+        let remap = |id: u32| match u16::try_from(id).ok().and_then(|id| mapping.get(&id)) {
+            Some(&new_id) => new_id as u32,
+            None => id,
+        };
 
-    // Parse Header
-    let version = parse_header_line(&mut reader, &mut line, consts::VERSION_PREFIX)?
-        .parse()
-        .map_err(|_| Error::InvalidFormat("Malformed version number".into()))?;
+        let mut new_ids: Vec<_> = self.modules.iter().map(|m| remap(m.id)).collect();
+        new_ids.sort_unstable();
+        if let Some((i, &id)) = new_ids.iter().enumerate().find(|&(i, &id)| id != i as u32) {
+            return Err(Error::ValidationError(if i > 0 && new_ids[i - 1] == id {
+                format!("Module ID mapping produces duplicate ID {id}")
+            } else {
+                format!("Module ID mapping leaves a gap: expected ID {i}, found {id}")
+            }));
+        }
 
-    if version != consts::SUPPORTED_FILE_VERSION {
-        return Err(Error::UnsupportedVersion(version));
+        for module in &mut self.modules {
+            module.id = remap(module.id);
+        }
+        self.modules.sort_by_key(|m| m.id);
+        for bb in &mut self.basic_blocks {
+            bb.module_id = remap(bb.module_id as u32) as u16;
+        }
+        self.debug_check_block_refs();
+        Ok(())
     }
 
-    let flavor = parse_header_line(&mut reader, &mut line, consts::FLAVOR_PREFIX)?.to_string();
-    let header = FileHeader { version, flavor };
+    /// Returns true if module bases never decrease in table order, i.e. if
+    /// [`sort_modules_by_base`](Self::sort_modules_by_base) would leave the
+    /// table order unchanged.
+    pub fn is_sorted_by_base(&self) -> bool {
+        self.modules
+            .windows(2)
+            .all(|pair| pair[0].base <= pair[1].base)
+    }
 
-    // Parse Module Table
-    let (modules, module_version) = parse_module_table(&mut reader, &mut line)?;
+    /// Sorts the module table by base address (keeping table order for equal
+    /// bases) and renumbers it as [`reindex_modules`](Self::reindex_modules)
+    /// does, so blocks follow their modules.
+    pub fn sort_modules_by_base(&mut self) {
+        self.modules.sort_by_key(|m| m.base);
+        self.reindex_modules();
+    }
 
-    // Parse Basic Block Table
-    let basic_blocks = parse_bb_table(&mut reader, &mut line)?;
+    /// Keeps only the modules for which the predicate returns `true`, then
+    /// renumbers them sequentially as [`reindex_modules`](Self::reindex_modules)
+    /// does. Blocks of removed modules are dropped and the rest are remapped.
+    pub fn retain_modules<F: FnMut(&ModuleEntry) -> bool>(&mut self, f: F) {
+        self.modules.retain(f);
+        self.reindex_modules();
+    }
 
-    let data = CoverageData {
-        header,
-        module_version,
-        modules,
-        basic_blocks,
-    };
-    data.validate()?;
-    Ok(data)
-}
+    /// Splits the data into one `CoverageData` per module, in table order.
+    ///
+    /// Each part holds a single module re-numbered to ID 0 together with its
+    /// blocks, and inherits the header, module table version and BB table
+    /// suffix. A `containing_id` pointing at the module itself becomes 0;
+    /// any other `containing_id` is cleared since its target is not carried
+    /// over. Modules without blocks yield parts with an empty BB table.
+    pub fn split_by_module(&self) -> Vec<CoverageData> {
+        self.modules
+            .iter()
+            .map(|module| {
+                let containing_id = module
+                    .containing_id
+                    .filter(|&id| id == module.id as i32)
+                    .map(|_| 0);
+                CoverageData {
+                    header: self.header.clone(),
+                    module_version: self.module_version,
+                    modules: vec![ModuleEntry {
+                        id: 0,
+                        containing_id,
+                        ..module.clone()
+                    }],
+                    basic_blocks: self
+                        .basic_blocks
+                        .iter()
+                        .filter(|bb| bb.module_id as u32 == module.id)
+                        .map(|bb| BasicBlock {
+                            module_id: 0,
+                            ..*bb
+                        })
+                        .collect(),
+                    bb_table_suffix: self.bb_table_suffix.clone(),
+                    source_columns: self.source_columns.clone(),
+                    extra_headers: self.extra_headers.clone(),
+                }
+            })
+            .collect()
+    }
 
-fn parse_header_line<'a>(
-    reader: &mut impl BufRead,
+    /// Builds a new `CoverageData` holding only the given modules, renumbered
+    /// `0..n` in the order of `ids`, and their blocks remapped to match.
+    ///
+    /// The header, module table version and BB table suffix are inherited.
+    /// A `containing_id` is remapped when its target is part of the subset
+    /// and cleared otherwise. Fails with `Error::ValidationError` if an ID
+    /// is unknown or repeated.
+    pub fn subset(&self, ids: &[u16]) -> Result<CoverageData> {
+        let mut mapping: HashMap<u32, u32> = HashMap::with_capacity(ids.len());
+        for (new_id, &id) in ids.iter().enumerate() {
+            if self.find_module(id).is_none() {
+                return Err(Error::ValidationError(format!("Unknown module ID: {id}")));
+            }
+            if mapping.insert(id as u32, new_id as u32).is_some() {
+                return Err(Error::ValidationError(format!(
+                    "Module ID {id} is listed more than once"
+                )));
+            }
+        }
+
+        let modules = ids
+            .iter()
+            .enumerate()
+            .map(|(new_id, &id)| {
+                let module = &self.modules[id as usize];
+                ModuleEntry {
+                    id: new_id as u32,
+                    containing_id: module
+                        .containing_id
+                        .and_then(|target| u32::try_from(target).ok())
+                        .and_then(|target| mapping.get(&target))
+                        .map(|&target| target as i32),
+                    ..module.clone()
+                }
+            })
+            .collect();
+        let subset = CoverageData {
+            header: self.header.clone(),
+            module_version: self.module_version,
+            modules,
+            basic_blocks: self
+                .basic_blocks
+                .iter()
+                .filter_map(|bb| {
+                    let &module_id = mapping.get(&(bb.module_id as u32))?;
+                    Some(BasicBlock {
+                        module_id: module_id as u16,
+                        ..*bb
+                    })
+                })
+                .collect(),
+            bb_table_suffix: self.bb_table_suffix.clone(),
+            source_columns: self.source_columns.clone(),
+            extra_headers: self.extra_headers.clone(),
+        };
+        subset.debug_check_block_refs();
+        Ok(subset)
+    }
+
+    /// Returns true if both module tables list the same modules, by path,
+    /// base and end, under the same IDs, so block module IDs from the two
+    /// can be compared directly.
+    pub fn same_module_layout(&self, other: &CoverageData) -> bool {
+        self.modules.len() == other.modules.len()
+            && self.modules.iter().zip(&other.modules).all(|(a, b)| {
+                a.id == b.id && a.path == b.path && a.base == b.base && a.end == b.end
+            })
+    }
+
+    /// Finds a module by its ID.
+    pub fn find_module(&self, id: u16) -> Option<&ModuleEntry> {
+        self.modules.get(id as usize).filter(|m| m.id == id as u32)
+    }
+
+    /// Finds the module a basic block belongs to.
+    pub fn module_for_block(&self, bb: &BasicBlock) -> Option<&ModuleEntry> {
+        self.find_module(bb.module_id)
+    }
+
+    /// Calculates the absolute address of a basic block, returning `None` if
+    /// it references an unknown module or the address would overflow `u64`.
+    pub fn absolute_address_of(&self, bb: &BasicBlock) -> Option<u64> {
+        bb.absolute_address_checked(self.module_for_block(bb)?)
+    }
+
+    /// Finds a module that contains a given memory address.
+    pub fn find_module_by_address(&self, addr: u64) -> Option<&ModuleEntry> {
+        self.modules.iter().find(|m| m.contains_address(addr))
+    }
+
+    /// Finds the first module whose path exactly matches `path`.
+    pub fn find_module_by_path(&self, path: &str) -> Option<&ModuleEntry> {
+        self.modules.iter().find(|m| m.path == path)
+    }
+
+    /// Finds all modules whose path contains `needle`.
+    /// An empty needle matches every module.
+    pub fn find_modules_by_path_substring(&self, needle: &str) -> Vec<&ModuleEntry> {
+        self.modules
+            .iter()
+            .filter(|m| m.path.contains(needle))
+            .collect()
+    }
+
+    /// Finds pairs of modules whose `[base, end)` ranges overlap.
+    ///
+    /// This is advisory only: overlapping modules are valid, but often point to
+    /// a tracer bug such as stale mappings. Each pair is reported once as
+    /// `(lower_id, higher_id)`, sorted. Empty or inverted ranges never overlap.
+    pub fn find_overlaps(&self) -> Vec<(u16, u16)> {
+        let mut sorted: Vec<_> = self.modules.iter().filter(|m| m.size() > 0).collect();
+        sorted.sort_by_key(|m| (m.base, m.id));
+
+        let mut overlaps = Vec::new();
+        for (i, a) in sorted.iter().enumerate() {
+            for b in sorted[i + 1..].iter().take_while(|b| b.base < a.end) {
+                let (lo, hi) = if a.id < b.id {
+                    (a.id, b.id)
+                } else {
+                    (b.id, a.id)
+                };
+                overlaps.push((lo as u16, hi as u16));
+            }
+        }
+        overlaps.sort_unstable();
+        overlaps
+    }
+
+    /// Lists each module path shared by more than one module, with the ids
+    /// that share it, in order of first appearance.
+    ///
+    /// Like [`find_overlaps`](Self::find_overlaps) this is advisory: reloaded
+    /// libraries legitimately appear more than once, but duplicates can also
+    /// be a tracer quirk.
+    pub fn duplicate_paths(&self) -> Vec<(String, Vec<u16>)> {
+        let mut groups: Vec<(String, Vec<u16>)> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for module in &self.modules {
+            match index.get(module.path.as_str()) {
+                Some(&i) => groups[i].1.push(module.id as u16),
+                None => {
+                    index.insert(&module.path, groups.len());
+                    groups.push((module.path.clone(), vec![module.id as u16]));
+                }
+            }
+        }
+        groups.retain(|(_, ids)| ids.len() > 1);
+        groups
+    }
+
+    /// Lists the ids of modules lacking a `checksum` or `timestamp` when the
+    /// writer will emit those columns, i.e. when another module has them.
+    ///
+    /// The writer fills the gaps with zero, which may misrepresent the data.
+    /// This is advisory; an empty list means nothing will be filled in.
+    pub fn validate_windows_fields(&self) -> Vec<u16> {
+        if !self.uses_windows_columns(&WriteOptions::default()) {
+            return Vec::new();
+        }
+        self.modules
+            .iter()
+            .filter(|m| m.checksum.is_none() || m.timestamp.is_none())
+            .map(|m| m.id as u16)
+            .collect()
+    }
+
+    /// Whether the module table is written with `checksum` and `timestamp`
+    /// columns under the given options.
+    fn uses_windows_columns(&self, options: &WriteOptions) -> bool {
+        self.module_version != ModuleTableVersion::Legacy
+            && (options.force_windows_columns
+                || self
+                    .modules
+                    .iter()
+                    .any(|m| m.checksum.is_some() || m.timestamp.is_some()))
+    }
+
+    /// Returns true if the module table should have an `entry` column. It is
+    /// only left out when the parsed file lacked it and no module has since
+    /// been given an entry point.
+    fn uses_entry_column(&self) -> bool {
+        let source_lacks_entry = self
+            .source_columns
+            .as_ref()
+            .is_some_and(|columns| !columns.iter().any(|c| c == "entry"));
+        self.module_version == ModuleTableVersion::Legacy
+            || !source_lacks_entry
+            || self.modules.iter().any(|m| m.entry.is_some())
+    }
+
+    /// Rewrites every module path through the supplied function.
+    ///
+    /// Useful for making paths from different traces comparable, e.g. with
+    /// [`basename`].
+    pub fn normalize_paths(&mut self, f: impl Fn(&str) -> String) {
+        for module in &mut self.modules {
+            module.path = f(&module.path);
+        }
+    }
+
+    /// Renames module paths for which the closure returns `Some`, leaving the
+    /// others unchanged, e.g. to swap a build prefix for an install prefix.
+    pub fn map_module_paths<F: FnMut(&str) -> Option<String>>(&mut self, mut f: F) {
+        for module in &mut self.modules {
+            if let Some(path) = f(&module.path) {
+                module.path = path;
+            }
+        }
+    }
+
+    /// Returns the set of `(module_id, start)` keys of all basic blocks.
+    ///
+    /// Block `size` is not part of the key, so two blocks at the same start
+    /// with different sizes count as one entry. Build the set once when
+    /// answering many membership queries.
+    pub fn covered_block_set(&self) -> HashSet<(u16, u32)> {
+        self.basic_blocks
+            .iter()
+            .map(|bb| (bb.module_id, bb.start))
+            .collect()
+    }
+
+    /// Returns true if any basic block starts at `start` in the given module,
+    /// regardless of its size.
+    ///
+    /// This scans all blocks; use [`covered_block_set`](Self::covered_block_set)
+    /// for repeated queries.
+    pub fn is_block_covered(&self, module_id: u16, start: u32) -> bool {
+        self.basic_blocks
+            .iter()
+            .any(|bb| bb.module_id == module_id && bb.start == start)
+    }
+
+    /// Returns the Jaccard similarity of the two runs' covered blocks, keyed
+    /// by `(module_id, start)` as in
+    /// [`covered_block_set`](Self::covered_block_set).
+    ///
+    /// Module IDs are compared directly, so this is only meaningful when
+    /// [`same_module_layout`](Self::same_module_layout) holds. Two runs
+    /// without blocks are considered identical.
+    pub fn similarity(&self, other: &CoverageData) -> f64 {
+        jaccard(&self.covered_block_set(), &other.covered_block_set())
+    }
+
+    /// Returns a map of the given module's bytes, with `true` for every byte
+    /// offset covered by at least one block.
+    ///
+    /// The bitmap is sized to the module, and block bytes past the module end
+    /// are ignored. Returns `None` if the module does not exist, has `end`
+    /// below `base`, or is larger than 64 MiB.
+    pub fn module_coverage_bitmap(&self, id: u16) -> Option<Vec<bool>> {
+        let size = self
+            .find_module(id)?
+            .size_checked()
+            .filter(|&size| size <= consts::MAX_BITMAP_MODULE_SIZE)? as usize;
+        let mut bitmap = vec![false; size];
+        for bb in self.basic_blocks.iter().filter(|bb| bb.module_id == id) {
+            let start = (bb.start as usize).min(size);
+            let end = (start + bb.size as usize).min(size);
+            bitmap[start..end].fill(true);
+        }
+        Some(bitmap)
+    }
+
+    /// Appends a basic block, failing with `Error::ValidationError` instead
+    /// of pushing if it references a module that does not exist.
+    pub fn push_block(&mut self, bb: BasicBlock) -> Result<()> {
+        if self.find_module(bb.module_id).is_none() {
+            return Err(Error::ValidationError(format!(
+                "Basic block references invalid module ID: {}",
+                bb.module_id
+            )));
+        }
+        self.basic_blocks.push(bb);
+        Ok(())
+    }
+
+    /// Appends blocks given as binary BB table records, 8 bytes each, as
+    /// produced by [`basic_blocks_to_bytes`](Self::basic_blocks_to_bytes).
+    ///
+    /// Fails with `Error::ValidationError`, appending nothing, if the length
+    /// is not a multiple of 8 or a record references a module that does not
+    /// exist.
+    pub fn append_raw_bb_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.check_raw_bb_bytes(bytes)?;
+        self.basic_blocks.extend(decode_raw_bb_bytes(bytes));
+        Ok(())
+    }
+
+    /// Checks the length and module references of raw BB records.
+    fn check_raw_bb_bytes(&self, bytes: &[u8]) -> Result<()> {
+        if !bytes.len().is_multiple_of(consts::BB_ENTRY_SIZE) {
+            return Err(Error::ValidationError(format!(
+                "Raw BB data length {} is not a multiple of {}",
+                bytes.len(),
+                consts::BB_ENTRY_SIZE
+            )));
+        }
+        if let Some(bb) =
+            decode_raw_bb_bytes(bytes).find(|bb| self.find_module(bb.module_id).is_none())
+        {
+            return Err(Error::ValidationError(format!(
+                "Basic block references invalid module ID: {}",
+                bb.module_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Records a basic block given its absolute address, resolving the
+    /// containing module and the offset from its base.
+    ///
+    /// Fails with `Error::ValidationError` if no module contains `addr` or the
+    /// offset does not fit in a `u32`.
+    pub fn add_absolute_hit(&mut self, addr: u64, size: u16) -> Result<()> {
+        let module = self.find_module_by_address(addr).ok_or_else(|| {
+            Error::ValidationError(format!("No module contains address 0x{addr:x}"))
+        })?;
+        let start = u32::try_from(addr - module.base).map_err(|_| {
+            Error::ValidationError(format!(
+                "Offset of address 0x{addr:x} in module {} exceeds u32",
+                module.id
+            ))
+        })?;
+        let module_id = module.id as u16;
+        self.basic_blocks.push(BasicBlock {
+            start,
+            size,
+            module_id,
+        });
+        Ok(())
+    }
+
+    /// Removes all basic blocks, keeping the header and module table.
+    pub fn clear_coverage(&mut self) {
+        self.basic_blocks.clear();
+    }
+
+    /// Returns a copy with the header and module table but no basic blocks,
+    /// for sharing just the module layout. It is written with
+    /// `BB Table: 0 bbs`, even if the source had a custom BB table suffix.
+    pub fn module_table_only(&self) -> CoverageData {
+        CoverageData {
+            header: self.header.clone(),
+            module_version: self.module_version,
+            modules: self.modules.clone(),
+            basic_blocks: Vec::new(),
+            bb_table_suffix: None,
+            source_columns: self.source_columns.clone(),
+            extra_headers: self.extra_headers.clone(),
+        }
+    }
+
+    /// Keeps only the basic blocks for which the predicate returns `true`.
+    pub fn retain_blocks<F: FnMut(&BasicBlock) -> bool>(&mut self, f: F) {
+        self.basic_blocks.retain(f);
+    }
+
+    /// Collapses blocks sharing a `(module_id, start)` into a single entry
+    /// with the largest observed size, keeping first-occurrence order.
+    ///
+    /// This is lossy: unlike exact deduplication, smaller extents reported
+    /// for the same start are discarded, as are any repeat counts.
+    pub fn coalesce_blocks_by_start(&mut self) {
+        let mut index: HashMap<(u16, u32), usize> = HashMap::new();
+        let mut coalesced: Vec<BasicBlock> = Vec::with_capacity(self.basic_blocks.len());
+        for bb in &self.basic_blocks {
+            match index.get(&(bb.module_id, bb.start)) {
+                Some(&i) => coalesced[i].size = coalesced[i].size.max(bb.size),
+                None => {
+                    index.insert((bb.module_id, bb.start), coalesced.len());
+                    coalesced.push(*bb);
+                }
+            }
+        }
+        self.basic_blocks = coalesced;
+    }
+
+    /// Merges abutting or overlapping blocks within each module into single
+    /// larger blocks, leaving the blocks sorted by module ID and start.
+    ///
+    /// This is lossy: the original block boundaries, order and repeat counts
+    /// are not kept. Blocks whose merged size would exceed `u16::MAX` are
+    /// left separate.
+    pub fn merge_adjacent_blocks(&mut self) {
+        self.basic_blocks.sort_by_key(|bb| (bb.module_id, bb.start));
+        let mut merged: Vec<BasicBlock> = Vec::with_capacity(self.basic_blocks.len());
+        for bb in &self.basic_blocks {
+            if let Some(last) = merged.last_mut().filter(|last| {
+                last.module_id == bb.module_id
+                    && bb.start as u64 <= last.start as u64 + last.size as u64
+            }) {
+                let end =
+                    (last.start as u64 + last.size as u64).max(bb.start as u64 + bb.size as u64);
+                if let Ok(size) = u16::try_from(end - last.start as u64) {
+                    last.size = size;
+                    continue;
+                }
+            }
+            merged.push(*bb);
+        }
+        self.basic_blocks = merged;
+    }
+
+    /// Returns an iterator pairing each basic block with its module.
+    /// Blocks referencing an unknown module ID are skipped.
+    pub fn resolved_blocks(&self) -> impl Iterator<Item = ResolvedBlock<'_>> {
+        self.basic_blocks.iter().filter_map(|bb| {
+            self.find_module(bb.module_id).map(|module| ResolvedBlock {
+                module,
+                offset: bb.start,
+                size: bb.size,
+            })
+        })
+    }
+
+    /// Returns the sum of all basic block sizes, counting overlapping blocks
+    /// multiple times.
+    pub fn total_covered_bytes(&self) -> u64 {
+        self.basic_blocks.iter().map(|bb| bb.size as u64).sum()
+    }
+
+    /// Returns the number of distinct bytes covered by basic blocks.
+    ///
+    /// Unlike [`total_covered_bytes`](Self::total_covered_bytes), overlapping
+    /// block ranges within a module are merged before summing.
+    pub fn unique_covered_bytes(&self) -> u64 {
+        merged_range_bytes(self.basic_blocks.iter().map(block_range).collect())
+    }
+
+    /// Estimates the number of covered instructions for architectures with
+    /// fixed-width instructions, e.g. `insn_size = 4` on AArch64.
+    ///
+    /// Divides [`unique_covered_bytes`](Self::unique_covered_bytes) by
+    /// `insn_size`, rounding down. Returns 0 if `insn_size` is 0.
+    pub fn estimated_instruction_count(&self, insn_size: u32) -> u64 {
+        self.unique_covered_bytes()
+            .checked_div(insn_size as u64)
+            .unwrap_or(0)
+    }
+
+    /// Formats a basic block as a detailed listing row with its module ID,
+    /// offset, size, absolute address and module path, appending
+    /// ` -> symbol` when the resolver finds a name.
+    ///
+    /// Returns `None` if the block references an unknown module.
+    pub fn format_block(&self, bb: &BasicBlock, resolver: &impl SymbolResolver) -> Option<String> {
+        let module = self.module_for_block(bb)?;
+        let mut row = format!(
+            "{:<8} 0x{:<11x} {:<8} 0x{:<15x} {}",
+            bb.module_id,
+            bb.start,
+            bb.size,
+            bb.absolute_address(module),
+            module.path
+        );
+        if let Some(symbol) = resolver.resolve(&module.path, bb.start) {
+            row.push_str(" -> ");
+            row.push_str(&symbol);
+        }
+        Some(row)
+    }
+
+    /// Builds an [`IntervalIndex`] for repeated address-to-block lookups.
+    pub fn interval_index(&self) -> IntervalIndex<'_> {
+        IntervalIndex::new(self)
+    }
+
+    /// Returns the basic blocks whose absolute start address lies in
+    /// `[lo, hi)`. Blocks referencing an unknown module are skipped.
+    pub fn blocks_in_address_range(&self, lo: u64, hi: u64) -> Vec<&BasicBlock> {
+        self.basic_blocks
+            .iter()
+            .filter(|bb| {
+                self.absolute_address_of(bb)
+                    .is_some_and(|addr| addr >= lo && addr < hi)
+            })
+            .collect()
+    }
+
+    /// Returns the lowest absolute block start and highest absolute block end
+    /// across all blocks, or `None` if there are none.
+    ///
+    /// Blocks referencing an unknown module or whose range would overflow
+    /// `u64` are ignored.
+    pub fn covered_address_bounds(&self) -> Option<(u64, u64)> {
+        self.basic_blocks
+            .iter()
+            .filter_map(|bb| {
+                let start = self.absolute_address_of(bb)?;
+                Some((start, start.checked_add(bb.size as u64)?))
+            })
+            .reduce(|(lo, hi), (start, end)| (lo.min(start), hi.max(end)))
+    }
+
+    /// Encodes all basic blocks in the binary BB table layout used by the
+    /// writer: one 8-byte little-endian record per block.
+    pub fn basic_blocks_to_bytes(&self) -> Vec<u8> {
+        self.basic_blocks
+            .iter()
+            .flat_map(|bb| bb.to_bytes())
+            .collect()
+    }
+
+    /// Serializes the whole structure, including every optional module field,
+    /// into a compact binary form for caching.
+    ///
+    /// This is not the drcov on-disk format; read it back with
+    /// [`from_bincode`](Self::from_bincode).
+    #[cfg(feature = "bincode")]
+    pub fn to_bincode(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| Error::from_bincode(*e))
+    }
+
+    /// Deserializes data produced by [`to_bincode`](Self::to_bincode) and
+    /// validates it.
+    #[cfg(feature = "bincode")]
+    pub fn from_bincode(bytes: &[u8]) -> Result<CoverageData> {
+        let data: CoverageData =
+            bincode::deserialize(bytes).map_err(|e| Error::from_bincode(*e))?;
+        data.validate()?;
+        Ok(data)
+    }
+
+    /// Writes the coverage as a text dump in the style of DynamoRIO's
+    /// `-dump_text` mode: the usual header and module table, then a
+    /// `BB Table:` line and one `module[ID]: 0xOFFSET, SIZE` line per block
+    /// instead of the binary records.
+    ///
+    /// This is a best-effort reproduction for tools that consume the text
+    /// dump; exact column widths may differ from a given DynamoRIO build.
+    pub fn write_dynamorio_text<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.validate()?;
+        write_text_header(self, w, &WriteOptions::default())?;
+        writeln!(
+            w,
+            "{}{} {}",
+            consts::BB_TABLE_PREFIX,
+            self.basic_blocks.len(),
+            consts::BB_TABLE_SUFFIX
+        )?;
+        writeln!(w, "module id, start, size:")?;
+        for bb in &self.basic_blocks {
+            writeln!(
+                w,
+                "module[{:3}]: 0x{:016x}, {:3}",
+                bb.module_id, bb.start, bb.size
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes one `module_basename+0xOFFSET` line per basic block, the
+    /// plain-text format accepted by Lighthouse and similar viewers.
+    /// Blocks referencing an unknown module are skipped.
+    pub fn write_module_offset_text<W: Write>(&self, w: &mut W) -> Result<()> {
+        for block in self.resolved_blocks() {
+            writeln!(w, "{}+0x{:x}", basename(block.module_path()), block.offset)?;
+        }
+        Ok(())
+    }
+
+    /// Computes a coverage summary for every module in a single pass over
+    /// the basic blocks. Blocks referencing an unknown module are ignored.
+    pub fn module_summaries(&self) -> Vec<ModuleSummary> {
+        let mut totals = vec![(0usize, 0u64); self.modules.len()];
+        for bb in &self.basic_blocks {
+            if let Some((count, bytes)) = totals.get_mut(bb.module_id as usize) {
+                *count += 1;
+                *bytes += bb.size as u64;
+            }
+        }
+
+        self.modules
+            .iter()
+            .zip(totals)
+            .map(|(module, (block_count, covered_bytes))| {
+                let size = module.size();
+                ModuleSummary {
+                    id: module.id,
+                    path: module.path.clone(),
+                    size,
+                    block_count,
+                    covered_bytes,
+                    coverage_ratio: module
+                        .size_checked()
+                        .filter(|&size| size > 0)
+                        .map(|size| covered_bytes as f64 / size as f64),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns modules ordered by covered bytes, with ties broken by ascending
+    /// module ID.
+    pub fn modules_by_coverage(&self, descending: bool) -> Vec<&ModuleEntry> {
+        let summaries = self.module_summaries();
+        let mut ranked: Vec<_> = self
+            .modules
+            .iter()
+            .zip(summaries.iter().map(|s| s.covered_bytes))
+            .collect();
+        ranked.sort_by(|(a, a_bytes), (b, b_bytes)| {
+            let by_bytes = if descending {
+                b_bytes.cmp(a_bytes)
+            } else {
+                a_bytes.cmp(b_bytes)
+            };
+            by_bytes.then(a.id.cmp(&b.id))
+        });
+        ranked.into_iter().map(|(module, _)| module).collect()
+    }
+
+    /// Calculates coverage statistics, returning a map of module ID to basic block count.
+    pub fn get_coverage_stats(&self) -> HashMap<u16, usize> {
+        let mut stats = HashMap::new();
+        for bb in &self.basic_blocks {
+            *stats.entry(bb.module_id).or_insert(0) += 1;
+        }
+        stats
+    }
+
+    /// Like [`get_coverage_stats`](Self::get_coverage_stats), but keyed by
+    /// module path so counts can be compared across files whose module IDs
+    /// differ. Modules sharing a path are summed under one key, and blocks
+    /// referencing an unknown module are ignored.
+    pub fn coverage_stats_by_path(&self) -> HashMap<String, usize> {
+        let mut counts = vec![0usize; self.modules.len()];
+        for bb in &self.basic_blocks {
+            if let Some(count) = counts.get_mut(bb.module_id as usize) {
+                *count += 1;
+            }
+        }
+        let mut stats = HashMap::new();
+        for (module, count) in self.modules.iter().zip(counts) {
+            if count > 0 {
+                *stats.entry(module.path.clone()).or_insert(0) += count;
+            }
+        }
+        stats
+    }
+
+    /// Returns the number of modules with at least one basic block.
+    pub fn covered_module_count(&self) -> usize {
+        let mut covered = vec![false; self.modules.len()];
+        for bb in &self.basic_blocks {
+            if let Some(flag) = covered.get_mut(bb.module_id as usize) {
+                *flag = true;
+            }
+        }
+        covered.iter().filter(|&&c| c).count()
+    }
+
+    /// Computes the dataset-wide totals in a single pass over the blocks.
+    pub fn stats(&self) -> CoverageStats {
+        let mut total_bytes = 0;
+        let mut covered = vec![false; self.modules.len()];
+        let mut ranges = Vec::with_capacity(self.basic_blocks.len());
+        for bb in &self.basic_blocks {
+            total_bytes += bb.size as u64;
+            if let Some(flag) = covered.get_mut(bb.module_id as usize) {
+                *flag = true;
+            }
+            ranges.push(block_range(bb));
+        }
+
+        let covered_modules = covered.iter().filter(|&&c| c).count();
+        CoverageStats {
+            module_count: self.modules.len(),
+            block_count: self.basic_blocks.len(),
+            total_bytes,
+            unique_bytes: merged_range_bytes(ranges),
+            covered_modules,
+            uncovered_modules: self.modules.len() - covered_modules,
+        }
+    }
+}
+
+/// A one-line human-readable summary, e.g.
+/// `drcov v2 (flavor=my_tool, v4): 3 modules, 128 blocks, 4096 bytes`.
+///
+/// The byte count is [`CoverageData::total_covered_bytes`]. Use `to_writer`
+/// for the file format.
+impl Display for CoverageData {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "drcov v{} (flavor={}, {}): {} modules, {} blocks, {} bytes",
+            self.header.version,
+            self.header.flavor,
+            self.module_version,
+            self.module_count(),
+            self.block_count(),
+            self.total_covered_bytes()
+        )
+    }
+}
+
+/// Returns the final component of a module path, accepting both `/` and `\`
+/// as separators.
+///
+/// ```
+/// assert_eq!(drcov::basename("/usr/lib/libc.so.6"), "libc.so.6");
+/// assert_eq!(drcov::basename("C:\\Windows\\ntdll.dll"), "ntdll.dll");
+/// ```
+pub fn basename(path: &str) -> String {
+    path.rsplit(['/', '\\']).next().unwrap_or(path).to_string()
+}
+
+/// Parses a number the way module table fields are read: as hex when
+/// `0x`- or `0X`-prefixed and as decimal otherwise.
+///
+/// Surrounding whitespace is not trimmed.
+///
+/// ```
+/// assert_eq!(drcov::parse_number("0x401000"), Some(0x401000));
+/// assert_eq!(drcov::parse_number("4096"), Some(4096));
+/// assert_eq!(drcov::parse_number("0x"), None);
+/// ```
+pub fn parse_number(s: &str) -> Option<u64> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+/// Computes the pairwise [`similarity`](CoverageData::similarity) of every
+/// pair of runs, as a symmetric matrix with 1.0 on the diagonal.
+///
+/// Each run's block set is built once and reused for all of its pairs.
+pub fn overlap_matrix(sets: &[&CoverageData]) -> Vec<Vec<f64>> {
+    let keys: Vec<_> = sets.iter().map(|data| data.covered_block_set()).collect();
+    let mut matrix = vec![vec![1.0; keys.len()]; keys.len()];
+    for i in 0..keys.len() {
+        for j in i + 1..keys.len() {
+            let value = jaccard(&keys[i], &keys[j]);
+            matrix[i][j] = value;
+            matrix[j][i] = value;
+        }
+    }
+    matrix
+}
+
+/// Returns a block's `(module_id, start, end)` byte range within its module.
+fn block_range(bb: &BasicBlock) -> (u16, u64, u64) {
+    (
+        bb.module_id,
+        bb.start as u64,
+        bb.start as u64 + bb.size as u64,
+    )
+}
+
+/// Sums the lengths of `(module_id, start, end)` ranges, counting bytes
+/// covered by overlapping ranges within a module once.
+fn merged_range_bytes(mut ranges: Vec<(u16, u64, u64)>) -> u64 {
+    ranges.sort_unstable();
+
+    let mut total = 0;
+    let mut current: Option<(u16, u64, u64)> = None;
+    for (module_id, start, end) in ranges {
+        match &mut current {
+            Some((id, _, cur_end)) if *id == module_id && start <= *cur_end => {
+                *cur_end = (*cur_end).max(end);
+            }
+            _ => {
+                if let Some((_, cur_start, cur_end)) = current {
+                    total += cur_end - cur_start;
+                }
+                current = Some((module_id, start, end));
+            }
+        }
+    }
+    if let Some((_, cur_start, cur_end)) = current {
+        total += cur_end - cur_start;
+    }
+    total
+}
+
+/// Jaccard index of two sets, treating two empty sets as identical.
+fn jaccard<T: Eq + Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    let shared = a.intersection(b).count();
+    let union = a.len() + b.len() - shared;
+    if union == 0 {
+        return 1.0;
+    }
+    shared as f64 / union as f64
+}
+
+/// Parses a drcov file from a file path.
+///
+/// I/O errors are reported as `Error::IoWithPath` naming the file.
+pub fn from_file<P: AsRef<Path>>(path: P) -> Result<CoverageData> {
+    let path = path.as_ref();
+    File::open(path)
+        .map_err(Error::from)
+        .and_then(from_reader)
+        .map_err(|e| e.with_path(path))
+}
+
+/// Options controlling how drcov files are parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Fail if any bytes remain after the declared basic blocks, e.g. when
+    /// files were accidentally concatenated.
+    pub reject_trailing_data: bool,
+    /// Skip blank lines and lines starting with `#` before the version line
+    /// and between the text sections.
+    pub allow_comments: bool,
+    /// Match the `DRCOV VERSION:`, `DRCOV FLAVOR:`, `Module Table:` and
+    /// `BB Table:` prefixes without regard to ASCII case.
+    pub case_insensitive_headers: bool,
+    /// Trim surrounding whitespace from module paths (the default). When
+    /// disabled, the path after the final column separator is kept verbatim
+    /// apart from the single space following the comma and the line
+    /// terminator.
+    pub trim_path: bool,
+    /// Reject files whose module table declares more modules than this,
+    /// before reading any module lines.
+    pub max_modules: Option<usize>,
+    /// Reject files whose BB table declares more blocks than this, before
+    /// reading any block data.
+    pub max_blocks: Option<usize>,
+    /// Accept files with no `DRCOV FLAVOR:` line, going straight from the
+    /// version line to `Module Table:`. The flavor is then empty.
+    pub flavor_optional: bool,
+    /// Keep unrecognized `KEY: value` lines between the flavor line and the
+    /// module table in [`CoverageData::extra_headers`] instead of rejecting
+    /// the file.
+    pub preserve_extra_headers: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            reject_trailing_data: false,
+            allow_comments: false,
+            case_insensitive_headers: false,
+            trim_path: true,
+            max_modules: None,
+            max_blocks: None,
+            flavor_optional: false,
+            preserve_extra_headers: false,
+        }
+    }
+}
+
+/// Parses a drcov file from any reader.
+pub fn from_reader<R: Read>(reader: R) -> Result<CoverageData> {
+    from_reader_with_options(reader, &ParseOptions::default())
+}
+
+/// Parses a drcov file from any reader using the given options.
+pub fn from_reader_with_options<R: Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<CoverageData> {
+    let mut reader = BufReader::new(reader);
+    skip_bom(&mut reader)?;
+    parse_coverage(&mut reader, options)
+}
+
+/// Parses a drcov file whose BB table uses the non-standard 16-byte
+/// [`BasicBlockWide`] record layout of some custom tracers.
+///
+/// The returned `CoverageData` holds the header and module table with no
+/// basic blocks; the wide blocks are returned alongside it. Fails with
+/// `Error::ValidationError` if a block references a module that does not
+/// exist.
+pub fn from_reader_wide<R: Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<(CoverageData, Vec<BasicBlockWide>)> {
+    let mut reader = BufReader::new(reader);
+    skip_bom(&mut reader)?;
+    let mut line = String::new();
+    let header = parse_file_header(&mut reader, &mut line, options)?;
+    let (data, blocks) = parse_tables(
+        &mut reader,
+        &mut line,
+        header,
+        options,
+        BbRecordLayout::Wide,
+    )?;
+    check_wide_block_refs(&data, &blocks)?;
+    Ok((data, blocks))
+}
+
+/// Parses only the `DRCOV VERSION:` and `DRCOV FLAVOR:` lines, leaving the
+/// reader positioned at the module table.
+///
+/// Continue with [`from_reader_after_header`] to parse the rest.
+pub fn parse_header<R: BufRead>(reader: &mut R) -> Result<FileHeader> {
+    skip_bom(reader)?;
+    parse_file_header(reader, &mut String::new(), &ParseOptions::default())
+}
+
+/// Parses the module and BB tables from a reader whose header has already
+/// been consumed by [`parse_header`].
+///
+/// ```
+/// let bytes = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 0\nBB Table: 0 bbs\n";
+/// let mut reader = &bytes[..];
+/// let header = drcov::parse_header(&mut reader).unwrap();
+/// let coverage = drcov::from_reader_after_header(&mut reader, header).unwrap();
+/// assert_eq!(coverage.header.flavor, "test");
+/// ```
+pub fn from_reader_after_header<R: BufRead>(
+    reader: &mut R,
+    header: FileHeader,
+) -> Result<CoverageData> {
+    parse_after_header(reader, &mut String::new(), header, &ParseOptions::default())
+}
+
+/// Parses coverage written as two streams: the text header and module table
+/// in one, and the raw 8-byte BB records in the other.
+///
+/// A `BB Table:` line at the end of the text stream is optional, but if
+/// present it must declare `declared_count` blocks.
+pub fn from_split<R1: Read, R2: Read>(
+    header_and_modules: R1,
+    bb_binary: R2,
+    declared_count: usize,
+) -> Result<CoverageData> {
+    let options = ParseOptions::default();
+    let mut reader = BufReader::new(header_and_modules);
+    skip_bom(&mut reader)?;
+
+    let mut line = String::new();
+    let header = parse_file_header(&mut reader, &mut line, &options)?;
+    let module_table = parse_module_table(&mut reader, &mut line, &options)?;
+    let mut bb_table_suffix = None;
+    if read_section_line(&mut reader, &mut line, &options)? > 0 {
+        let (count, suffix) = parse_bb_table_header(&line, &options)?;
+        if count != declared_count {
+            return Err(Error::InvalidBbTable(format!(
+                "BB table declares {count} blocks, expected {declared_count}"
+            )));
+        }
+        bb_table_suffix = suffix;
+    }
+
+    let (basic_blocks, _) = read_bb_records(
+        &mut BufReader::new(bb_binary),
+        declared_count,
+        BbRecordLayout::Standard,
+    )?;
+
+    let data = CoverageData {
+        header,
+        module_version: module_table.version,
+        modules: module_table.modules,
+        basic_blocks,
+        bb_table_suffix,
+        source_columns: module_table.columns,
+        ..Default::default()
+    };
+    data.validate()?;
+    Ok(data)
+}
+
+/// Parses only the header and module table, for tools that need module
+/// metadata but not coverage.
+///
+/// The `BB Table:` line is checked when present, but the binary block
+/// records after it are never read.
+pub fn read_modules_only<R: Read>(
+    reader: R,
+) -> Result<(FileHeader, ModuleTableVersion, Vec<ModuleEntry>)> {
+    let options = ParseOptions::default();
+    let mut reader = BufReader::new(reader);
+    skip_bom(&mut reader)?;
+
+    let mut line = String::new();
+    let header = parse_file_header(&mut reader, &mut line, &options)?;
+    let module_table = parse_module_table(&mut reader, &mut line, &options)?;
+    if read_section_line(&mut reader, &mut line, &options)? > 0 {
+        parse_bb_table_header(&line, &options)?;
+    }
+
+    let data = CoverageData {
+        header,
+        module_version: module_table.version,
+        modules: module_table.modules,
+        ..Default::default()
+    };
+    data.validate()?;
+    Ok((data.header, data.module_version, data.modules))
+}
+
+/// Returns the block count declared by the `BB Table:` line without parsing
+/// the module table or reading the binary block records.
+///
+/// The version and flavor lines are checked; lines after them are skipped
+/// up to the `BB Table:` line, and the reader is left just past it. A file
+/// without a BB table has no blocks.
+///
+/// ```
+/// let bytes = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 0\nBB Table: 0 bbs\n";
+/// assert_eq!(drcov::count_blocks(&mut &bytes[..]).unwrap(), 0);
+/// ```
+pub fn count_blocks<R: BufRead>(reader: &mut R) -> Result<usize> {
+    let options = ParseOptions::default();
+    skip_bom(reader)?;
+    let mut line = String::new();
+    parse_file_header(reader, &mut line, &options)?;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(0);
+        }
+        if line.starts_with(consts::BB_TABLE_PREFIX) {
+            return parse_bb_table_header(&line, &options).map(|(count, _)| count);
+        }
+    }
+}
+
+/// Callbacks for [`parse_visit`], invoked in file order as each part is
+/// parsed.
+///
+/// All methods default to doing nothing, so a visitor only implements the
+/// ones it needs.
+pub trait DrcovVisitor {
+    /// Called once the version and flavor lines have been parsed.
+    fn on_header(&mut self, _header: &FileHeader) {}
+    /// Called for each module table entry, in ID order.
+    fn on_module(&mut self, _module: &ModuleEntry) {}
+    /// Called for each basic block, in file order.
+    fn on_block(&mut self, _block: &BasicBlock) {}
+}
+
+/// Parses a drcov file, passing each part to `visitor` as it is read instead
+/// of collecting modules and blocks into vectors.
+///
+/// Each block's module ID is checked against the module table as it is read,
+/// but checks that need the whole file, such as those in
+/// [`CoverageData::validate`], are not run.
+///
+/// ```
+/// struct Counter(usize);
+/// impl drcov::DrcovVisitor for Counter {
+///     fn on_block(&mut self, _block: &drcov::BasicBlock) {
+///         self.0 += 1;
+///     }
+/// }
+///
+/// let bytes = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 0\nBB Table: 0 bbs\n";
+/// let mut counter = Counter(0);
+/// drcov::parse_visit(&bytes[..], &mut counter).unwrap();
+/// assert_eq!(counter.0, 0);
+/// ```
+pub fn parse_visit<R: Read>(reader: R, visitor: &mut impl DrcovVisitor) -> Result<()> {
+    let options = ParseOptions::default();
+    let mut reader = BufReader::new(reader);
+    skip_bom(&mut reader)?;
+
+    let mut line = String::new();
+    let header = parse_file_header(&mut reader, &mut line, &options)?;
+    visitor.on_header(&header);
+
+    let mut module_count = 0usize;
+    parse_module_table_with(&mut reader, &mut line, &options, |module| {
+        visitor.on_module(&module);
+        module_count += 1;
+    })?;
+
+    // It's possible for the BB table to be missing if there are no blocks
+    if read_section_line(&mut reader, &mut line, &options)? == 0 {
+        return Ok(());
+    }
+    let (count, _) = parse_bb_table_header(&line, &options)?;
+    for_each_bb_record(&mut reader, count, consts::BB_ENTRY_SIZE, |entry| {
+        let block = BasicBlock::from_bytes(entry.try_into().unwrap());
+        if usize::from(block.module_id) >= module_count {
+            return Err(Error::ValidationError(format!(
+                "Basic block references invalid module ID: {}",
+                block.module_id
+            )));
+        }
+        visitor.on_block(&block);
+        Ok(())
+    })
+}
+
+/// Parses a stream of concatenated drcov sections, each running from a
+/// `DRCOV VERSION:` line through its BB table, until EOF.
+///
+/// A single-section file yields a one-element vector.
+pub fn from_reader_multi<R: Read>(reader: R) -> Result<Vec<CoverageData>> {
+    let mut reader = BufReader::new(reader);
+    skip_bom(&mut reader)?;
+    let mut sections = Vec::new();
+    loop {
+        sections.push(parse_coverage(&mut reader, &ParseOptions::default())?);
+        if reader.fill_buf()?.is_empty() {
+            return Ok(sections);
+        }
+    }
+}
+
+/// Parses a drcov file held entirely in memory.
+///
+/// The slice is read directly rather than through an intermediate buffer, so
+/// the BB binary section is copied only once, straight into the decode buffer.
+///
+/// ```
+/// let bytes = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 0\nBB Table: 0 bbs\n";
+/// let coverage = drcov::from_bytes(bytes).unwrap();
+/// assert_eq!(coverage.header.flavor, "test");
+/// ```
+pub fn from_bytes(mut data: &[u8]) -> Result<CoverageData> {
+    skip_bom(&mut data)?;
+    parse_coverage(&mut data, &ParseOptions::default())
+}
+
+/// Parses a drcov file from an async reader.
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader<R: tokio::io::AsyncRead + Unpin>(reader: R) -> Result<CoverageData> {
+    from_async_reader_with_options(reader, &ParseOptions::default()).await
+}
+
+/// Parses a drcov file from an async reader using the given options.
+///
+/// The text sections are read line by line up to the `BB Table:` line and
+/// parsed by the same code as [`from_reader_with_options`]. The BB table is
+/// then read record by record, so it is never buffered whole.
+#[cfg(feature = "tokio")]
+pub async fn from_async_reader_with_options<R: tokio::io::AsyncRead + Unpin>(
+    reader: R,
+    options: &ParseOptions,
+) -> Result<CoverageData> {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+    let mut reader = tokio::io::BufReader::new(reader);
+    let mut text = String::new();
+    loop {
+        let start = text.len();
+        if reader.read_line(&mut text).await? == 0
+            || bb_table_header_content(&text[start..], options).is_some()
+        {
+            break;
+        }
+    }
+
+    let mut text = text.as_bytes();
+    skip_bom(&mut text)?;
+    let mut line = String::new();
+    let header = parse_file_header(&mut text, &mut line, options)?;
+    let sections = parse_text_sections(&mut text, &mut line, header, options)?;
+
+    let count = sections.block_count();
+    let mut basic_blocks = Vec::with_capacity(count.min(consts::BB_CHUNK_RECORDS));
+    let mut record = [0u8; consts::BB_ENTRY_SIZE];
+    for complete in 0..count {
+        match reader.read_exact(&mut record).await {
+            Ok(_) => basic_blocks.push(BasicBlock::from_bytes(&record)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(truncated_bb_table_error(complete, count));
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if options.reject_trailing_data && reader.read(&mut [0u8; 1]).await? > 0 {
+        return Err(trailing_data_error());
+    }
+
+    sections.into_coverage(basic_blocks)
+}
+
+/// Parses every `.drcov` entry of a gzip-compressed tar archive, returning
+/// `(entry_name, coverage)` pairs in archive order.
+///
+/// Entries with other names are skipped. The first entry that fails to parse
+/// aborts the whole bundle; use [`from_tar_gz_lenient`] to collect per-entry
+/// errors instead.
+#[cfg(all(feature = "tar", feature = "flate2"))]
+pub fn from_tar_gz<R: Read>(reader: R) -> Result<Vec<(String, CoverageData)>> {
+    from_tar_gz_lenient(reader)?
+        .into_iter()
+        .map(|(name, result)| result.map(|coverage| (name, coverage)))
+        .collect()
+}
+
+/// Like [`from_tar_gz`], but keeps going when an entry fails to parse and
+/// returns each entry's result alongside its name.
+///
+/// Errors reading the archive itself still abort.
+#[cfg(all(feature = "tar", feature = "flate2"))]
+pub fn from_tar_gz_lenient<R: Read>(reader: R) -> Result<Vec<(String, Result<CoverageData>)>> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(reader));
+    let mut results = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        if !entry.header().entry_type().is_file() || !name.ends_with(".drcov") {
+            continue;
+        }
+        results.push((name, from_reader(entry)));
+    }
+    Ok(results)
+}
+
+/// Skips a UTF-8 byte order mark at the current position, if present.
+fn skip_bom(reader: &mut impl BufRead) -> Result<()> {
+    if reader.fill_buf()?.starts_with(consts::UTF8_BOM) {
+        reader.consume(consts::UTF8_BOM.len());
+    }
+    Ok(())
+}
+
+fn parse_coverage(reader: &mut impl BufRead, options: &ParseOptions) -> Result<CoverageData> {
+    let mut line = String::new();
+
+    // Parse Header
+    let header = parse_file_header(reader, &mut line, options)?;
+    parse_after_header(reader, &mut line, header, options)
+}
+
+/// Parses the module and BB tables following an already parsed header.
+fn parse_after_header(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    header: FileHeader,
+    options: &ParseOptions,
+) -> Result<CoverageData> {
+    let (data, _) = parse_tables(reader, line, header, options, BbRecordLayout::Standard)?;
+    Ok(data)
+}
+
+/// Parses the module and BB tables, decoding the BB records in `layout`.
+/// Wide records are returned separately; the data is validated either way.
+fn parse_tables(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    header: FileHeader,
+    options: &ParseOptions,
+    layout: BbRecordLayout,
+) -> Result<(CoverageData, Vec<BasicBlockWide>)> {
+    let sections = parse_text_sections(reader, line, header, options)?;
+    let (basic_blocks, wide_blocks) = read_bb_records(reader, sections.block_count(), layout)?;
+
+    if options.reject_trailing_data && reader.read(&mut [0u8; 1])? > 0 {
+        return Err(trailing_data_error());
+    }
+
+    Ok((sections.into_coverage(basic_blocks)?, wide_blocks))
+}
+
+/// The text sections of a file, from the header up to and including the
+/// `BB Table:` line.
+struct TextSections {
+    header: FileHeader,
+    extra_headers: Vec<String>,
+    module_table: ModuleTable,
+    /// Declared block count and header suffix, or `None` if the file ends
+    /// before the BB table.
+    bb_table: Option<(usize, Option<String>)>,
+}
+
+impl TextSections {
+    /// Number of BB records following the text sections.
+    fn block_count(&self) -> usize {
+        self.bb_table.as_ref().map_or(0, |&(count, _)| count)
+    }
+
+    /// Assembles the coverage data from the decoded blocks and validates it.
+    fn into_coverage(self, basic_blocks: Vec<BasicBlock>) -> Result<CoverageData> {
+        let data = CoverageData {
+            header: self.header,
+            module_version: self.module_table.version,
+            modules: self.module_table.modules,
+            basic_blocks,
+            bb_table_suffix: self.bb_table.and_then(|(_, suffix)| suffix),
+            source_columns: self.module_table.columns,
+            extra_headers: self.extra_headers,
+        };
+        data.validate()?;
+        Ok(data)
+    }
+}
+
+/// Parses the extra header lines, the module table and the `BB Table:` line
+/// following an already parsed header.
+fn parse_text_sections(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    header: FileHeader,
+    options: &ParseOptions,
+) -> Result<TextSections> {
+    let mut extra_headers = Vec::new();
+    if options.preserve_extra_headers {
+        loop {
+            if line.is_empty() {
+                read_section_line(reader, line, options)?;
+            }
+            let content = line.trim_end_matches(['\r', '\n']);
+            if !content.contains(':')
+                || strip_header_prefix(content.trim_start(), consts::MODULE_TABLE_PREFIX, options)
+                    .is_some()
+            {
+                break;
+            }
+            extra_headers.push(content.to_string());
+            line.clear();
+        }
+    }
+
+    // Parse Module Table
+    let module_table = parse_module_table(reader, line, options)?;
+
+    // Parse Basic Block Table header
+    let bb_table = parse_bb_table_line(reader, line, options)?;
+
+    Ok(TextSections {
+        header,
+        extra_headers,
+        module_table,
+        bb_table,
+    })
+}
+
+/// The error for bytes following the BB table with `reject_trailing_data`.
+fn trailing_data_error() -> Error {
+    Error::InvalidFormat("Unexpected trailing data after BB table".to_string())
+}
+
+/// Parses the `DRCOV VERSION:` and `DRCOV FLAVOR:` lines.
+///
+/// `line` is left empty, except when an absent flavor line is accepted; then
+/// it holds the module table header that was read in its place.
+fn parse_file_header(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    options: &ParseOptions,
+) -> Result<FileHeader> {
+    let version = parse_header_line(reader, line, consts::VERSION_PREFIX, options)?
+        .parse()
+        .map_err(|_| Error::InvalidFormat("Malformed version number".into()))?;
+
+    if version != consts::SUPPORTED_FILE_VERSION {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    read_section_line(reader, line, options)?;
+    if options.flavor_optional
+        && strip_header_prefix(line.trim_start(), consts::MODULE_TABLE_PREFIX, options).is_some()
+    {
+        return Ok(FileHeader {
+            version,
+            flavor: String::new(),
+            flavor_metadata: None,
+        });
+    }
+
+    let flavor_line = strip_header_line(line, consts::FLAVOR_PREFIX, options)?;
+    let (flavor, flavor_metadata) = match flavor_line.split_once('\t') {
+        Some((flavor, metadata)) => (flavor.to_string(), Some(metadata.to_string())),
+        None => (flavor_line.to_string(), None),
+    };
+    let header = FileHeader {
+        version,
+        flavor,
+        flavor_metadata,
+    };
+    line.clear();
+    Ok(header)
+}
+
+/// Reads the next section line into `line`, skipping blank and `#` comment
+/// lines when `allow_comments` is set. Returns the number of bytes read.
+fn read_section_line(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    options: &ParseOptions,
+) -> Result<usize> {
+    loop {
+        line.clear();
+        let read = reader.read_line(line)?;
+        let trimmed = line.trim();
+        if read == 0 || !options.allow_comments || !(trimmed.is_empty() || trimmed.starts_with('#'))
+        {
+            return Ok(read);
+        }
+    }
+}
+
+fn parse_header_line<'a>(
+    reader: &mut impl BufRead,
     line: &'a mut String,
     prefix: &str,
+    options: &ParseOptions,
 ) -> Result<&'a str> {
-    line.clear();
-    if reader.read_line(line)? == 0 {
+    read_section_line(reader, line, options)?;
+    strip_header_line(line, prefix, options)
+}
+
+/// Extracts the value of a header line already read into `line`.
+fn strip_header_line<'a>(line: &'a str, prefix: &str, options: &ParseOptions) -> Result<&'a str> {
+    if line.is_empty() {
         return Err(Error::InvalidFormat(format!(
             "Expected header line with prefix '{prefix}', but found EOF"
         )));
     }
-    line.strip_suffix('\n')
-        .unwrap_or(line.as_str())
-        .strip_prefix(prefix)
-        .ok_or_else(|| {
-            Error::InvalidFormat(format!(
-                "Invalid header line format, expected prefix '{prefix}'"
-            ))
-        })
+    let content = line.strip_suffix('\n').unwrap_or(line);
+    strip_header_prefix(content, prefix, options).ok_or_else(|| {
+        Error::InvalidFormat(format!(
+            "Invalid header line format, expected prefix '{prefix}'"
+        ))
+    })
+}
+
+/// Strips a section header prefix, ignoring ASCII case when
+/// `case_insensitive_headers` is set.
+fn strip_header_prefix<'a>(s: &'a str, prefix: &str, options: &ParseOptions) -> Option<&'a str> {
+    if options.case_insensitive_headers {
+        let head = s.get(..prefix.len())?;
+        head.eq_ignore_ascii_case(prefix)
+            .then(|| &s[prefix.len()..])
+    } else {
+        s.strip_prefix(prefix)
+    }
+}
+
+/// The module table section of a parsed file.
+struct ModuleTable {
+    version: ModuleTableVersion,
+    /// The `Columns:` header, absent for legacy tables.
+    columns: Option<Vec<String>>,
+    modules: Vec<ModuleEntry>,
 }
 
+/// Parses the module table, starting from the header line in `line` or, if
+/// `line` is empty, the next section line.
 fn parse_module_table(
     reader: &mut impl BufRead,
     line: &mut String,
-) -> Result<(Vec<ModuleEntry>, ModuleTableVersion)> {
-    line.clear();
-    reader.read_line(line)?;
-    let content = line
-        .trim()
-        .strip_prefix(consts::MODULE_TABLE_PREFIX)
+    options: &ParseOptions,
+) -> Result<ModuleTable> {
+    let mut modules = Vec::new();
+    let (version, columns) =
+        parse_module_table_with(reader, line, options, |module| modules.push(module))?;
+    Ok(ModuleTable {
+        version,
+        columns,
+        modules,
+    })
+}
+
+/// Like [`parse_module_table`], but hands each module to `on_module` as it is
+/// parsed. Returns the table version and the source `Columns:` header.
+fn parse_module_table_with(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    options: &ParseOptions,
+    mut on_module: impl FnMut(ModuleEntry),
+) -> Result<(ModuleTableVersion, Option<Vec<String>>)> {
+    if line.is_empty() {
+        read_section_line(reader, line, options)?;
+    }
+    let content = strip_header_prefix(line.trim(), consts::MODULE_TABLE_PREFIX, options)
         .ok_or_else(|| Error::InvalidModuleTable("Missing or malformed header".to_string()))?;
 
-    let (version, count) = if let Some(version_part) = content.strip_prefix("version ") {
-        let parts: Vec<_> = version_part.split(',').collect();
-        if parts.len() != 2 {
-            return Err(Error::InvalidModuleTable(
-                "Invalid versioned header format".to_string(),
-            ));
-        }
-        let ver_num = parts[0]
+    // Whitespace around the comma and after the keywords is optional, since
+    // hand-edited files are often inconsistent about it.
+    let (version, count) = if let Some(version_part) = content.strip_prefix("version") {
+        let (ver_str, count_part) = version_part.split_once(',').ok_or_else(|| {
+            Error::InvalidModuleTable("Invalid versioned header format".to_string())
+        })?;
+        let ver_num = ver_str
             .trim()
             .parse::<u32>()
             .map_err(|_| Error::InvalidModuleTable("Invalid version number".to_string()))?;
-        let count_str = parts[1]
+        let count_str = count_part
             .trim()
-            .strip_prefix("count ")
+            .strip_prefix("count")
             .ok_or_else(|| Error::InvalidModuleTable("Missing count".to_string()))?;
         let count = count_str
+            .trim()
             .parse::<usize>()
             .map_err(|_| Error::InvalidModuleTable("Invalid count value".to_string()))?;
         (
@@ -395,6 +2538,14 @@ fn parse_module_table(
             },
             count,
         )
+    } else if let Some(count_str) = content.strip_prefix("count") {
+        // Some tools write `count N` without a version; a Columns line still
+        // follows, so it is read as a V2 table.
+        let count = count_str
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| Error::InvalidModuleTable("Invalid count value".to_string()))?;
+        (ModuleTableVersion::V2, count)
     } else {
         (
             ModuleTableVersion::Legacy,
@@ -404,18 +2555,39 @@ fn parse_module_table(
         )
     };
 
-    let columns = if version != ModuleTableVersion::Legacy {
-        line.clear();
-        reader.read_line(line)?;
+    if let Some(limit) = options.max_modules.filter(|&limit| count > limit) {
+        return Err(Error::InvalidFormat(format!(
+            "module count {count} exceeds limit {limit}"
+        )));
+    }
+
+    let source_columns = if version != ModuleTableVersion::Legacy {
+        read_section_line(reader, line, options)?;
         let columns_str = line
             .trim()
-            .strip_prefix(consts::COLUMNS_PREFIX)
+            .strip_prefix(consts::COLUMNS_PREFIX.trim_end())
             .ok_or_else(|| Error::InvalidModuleTable("Missing columns header".to_string()))?;
-        columns_str
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .collect::<Vec<_>>()
+        // Columns are comma-separated per the format, but some tools use tabs.
+        // If the list contains a tab, both tabs and commas separate columns
+        // and empty names are dropped. Module rows are always comma-separated.
+        let columns: Vec<_> = if columns_str.contains('\t') {
+            columns_str
+                .split(['\t', ','])
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect()
+        } else {
+            columns_str
+                .split(',')
+                .map(|s| s.trim().to_string())
+                .collect()
+        };
+        Some(columns)
     } else {
+        None
+    };
+    let columns = source_columns.clone().unwrap_or_else(|| {
         vec![
             "id".to_string(),
             "base".to_string(),
@@ -423,13 +2595,26 @@ fn parse_module_table(
             "entry".to_string(),
             "path".to_string(),
         ]
-    };
+    });
 
-    let mut modules = Vec::with_capacity(count);
     for i in 0..count {
         line.clear();
-        reader.read_line(line)?;
-        let module = parse_module_entry(line.trim(), &columns)?;
+        if reader.read_line(line)? == 0 {
+            return Err(Error::InvalidModuleTable(format!(
+                "Expected {count} modules, found {i} before EOF"
+            )));
+        }
+        if strip_header_prefix(line, consts::BB_TABLE_PREFIX, options).is_some() {
+            return Err(Error::InvalidModuleTable(format!(
+                "Expected {count} modules, found {i} before BB table"
+            )));
+        }
+        let entry_line = if options.trim_path {
+            line.trim()
+        } else {
+            line.trim_start().trim_end_matches(['\r', '\n'])
+        };
+        let module = parse_module_entry(entry_line, &columns, options)?;
         if module.id != i as u32 {
             return Err(Error::InvalidModuleTable(format!(
                 "Non-sequential module ID. Expected {i}, got {}",
@@ -437,14 +2622,28 @@ fn parse_module_table(
             )));
         }
         // No normalization needed - 'start' is already mapped to 'base' in parse_module_entry
-        modules.push(module);
+        on_module(module);
     }
 
-    Ok((modules, version))
+    Ok((version, source_columns))
 }
 
-fn parse_module_entry(line: &str, columns: &[String]) -> Result<ModuleEntry> {
-    let values: Vec<_> = line.splitn(columns.len(), ',').map(|s| s.trim()).collect();
+fn parse_module_entry(
+    line: &str,
+    columns: &[String],
+    options: &ParseOptions,
+) -> Result<ModuleEntry> {
+    let values: Vec<_> = line
+        .splitn(columns.len(), ',')
+        .enumerate()
+        .map(|(i, s)| {
+            if !options.trim_path && i + 1 == columns.len() && columns[i] == "path" {
+                s.strip_prefix(' ').unwrap_or(s)
+            } else {
+                s.trim()
+            }
+        })
+        .collect();
     if values.len() != columns.len() {
         return Err(Error::InvalidModuleTable(format!(
             "Column count mismatch in line: {line}"
@@ -454,24 +2653,36 @@ fn parse_module_entry(line: &str, columns: &[String]) -> Result<ModuleEntry> {
     let map: HashMap<_, _> = columns.iter().zip(values.iter()).collect();
     let mut entry = ModuleEntry::default();
 
-    let parse_u64 = |key: &str| {
+    let parse_u64 = |key: &str| -> Result<Option<u64>> {
         map.get(&key.to_string())
-            .and_then(|s| u64::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+            .map(|s| {
+                if s.starts_with('-') {
+                    return Err(Error::InvalidModuleTable(format!(
+                        "Negative '{key}' value not supported: {s}"
+                    )));
+                }
+                parse_number(s)
+                    .ok_or_else(|| Error::InvalidModuleTable(format!("Invalid '{key}' value: {s}")))
+            })
+            .transpose()
     };
-    let parse_u32 = |key: &str| {
-        map.get(&key.to_string())
-            .and_then(|s| u32::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+    let parse_u32 = |key: &str| -> Result<Option<u32>> {
+        parse_u64(key)?
+            .map(|v| {
+                u32::try_from(v).map_err(|_| {
+                    Error::InvalidModuleTable(format!("Value out of range for '{key}': {v}"))
+                })
+            })
+            .transpose()
     };
 
     entry.id = map
         .get(&"id".to_string())
         .and_then(|s| s.parse().ok())
         .ok_or_else(|| Error::InvalidModuleTable("Missing or invalid 'id'".to_string()))?;
-    entry.base = parse_u64("base")
-        .or_else(|| parse_u64("start"))
-        .unwrap_or(0);
-    entry.end = parse_u64("end").unwrap_or(0);
-    entry.entry = parse_u64("entry").unwrap_or(0);
+    entry.base = parse_u64("base")?.or(parse_u64("start")?).unwrap_or(0);
+    entry.end = parse_u64("end")?.unwrap_or(0);
+    entry.entry = parse_u64("entry")?;
     entry.path = map
         .get(&"path".to_string())
         .map(|s| s.to_string())
@@ -479,62 +2690,325 @@ fn parse_module_entry(line: &str, columns: &[String]) -> Result<ModuleEntry> {
     entry.containing_id = map
         .get(&"containing_id".to_string())
         .and_then(|s| s.parse().ok());
-    entry.offset = parse_u64("offset");
-    entry.checksum = parse_u32("checksum");
-    entry.timestamp = parse_u32("timestamp");
+    entry.offset = parse_u64("offset")?;
+    entry.checksum = parse_u32("checksum")?;
+    entry.timestamp = parse_u32("timestamp")?;
+
+    Ok(entry)
+}
+
+/// Returns the text after the `BB Table:` prefix, or `None` if `line` is
+/// not a BB table header.
+fn bb_table_header_content<'a>(line: &'a str, options: &ParseOptions) -> Option<&'a str> {
+    strip_header_prefix(line.trim(), consts::BB_TABLE_PREFIX, options)
+}
+
+/// Parses a `BB Table: N bbs` line into the block count and the suffix when
+/// it differs from the default `bbs`.
+fn parse_bb_table_header(line: &str, options: &ParseOptions) -> Result<(usize, Option<String>)> {
+    let content = bb_table_header_content(line, options)
+        .ok_or_else(|| Error::InvalidBbTable("Missing or malformed header".to_string()))?;
 
-    Ok(entry)
+    let (count_str, suffix) = content
+        .split_once(char::is_whitespace)
+        .map_or((content, ""), |(count, suffix)| (count, suffix.trim()));
+    let count = if count_str.is_empty() { "0" } else { count_str }
+        .parse::<usize>()
+        .map_err(|_| Error::InvalidBbTable("Invalid block count".to_string()))?;
+    let suffix = (suffix != consts::BB_TABLE_SUFFIX).then(|| suffix.to_string());
+    Ok((count, suffix))
 }
 
-fn parse_bb_table(reader: &mut impl BufRead, line: &mut String) -> Result<Vec<BasicBlock>> {
-    line.clear();
+/// Reads and parses the `BB Table:` line, returning the declared block count
+/// and the header suffix when it differs from the default `bbs`, or `None` if
+/// the file ends before the BB table.
+fn parse_bb_table_line(
+    reader: &mut impl BufRead,
+    line: &mut String,
+    options: &ParseOptions,
+) -> Result<Option<(usize, Option<String>)>> {
     // It's possible for the BB table to be missing if there are no blocks
-    if reader.read_line(line)? == 0 {
-        return Ok(Vec::new());
+    if read_section_line(reader, line, options)? == 0 {
+        return Ok(None);
     }
-    let content = line
-        .trim()
-        .strip_prefix(consts::BB_TABLE_PREFIX)
-        .ok_or_else(|| Error::InvalidBbTable("Missing or malformed header".to_string()))?;
+    let (count, suffix) = parse_bb_table_header(line, options)?;
+    if let Some(limit) = options.max_blocks.filter(|&limit| count > limit) {
+        return Err(Error::InvalidFormat(format!(
+            "block count {count} exceeds limit {limit}"
+        )));
+    }
+    Ok(Some((count, suffix)))
+}
 
-    let count = content
-        .split_whitespace()
-        .next()
-        .unwrap_or("0")
-        .parse::<usize>()
-        .map_err(|_| Error::InvalidBbTable("Invalid block count".to_string()))?;
+/// Decodes `count` binary BB records in the given layout.
+fn read_bb_records(
+    reader: &mut impl Read,
+    count: usize,
+    layout: BbRecordLayout,
+) -> Result<(Vec<BasicBlock>, Vec<BasicBlockWide>)> {
+    let mut blocks = Vec::new();
+    let mut wide_blocks = Vec::new();
+    for_each_bb_record(reader, count, layout.record_size(), |entry| {
+        match layout {
+            BbRecordLayout::Standard => {
+                blocks.push(BasicBlock::from_bytes(entry.try_into().unwrap()))
+            }
+            BbRecordLayout::Wide => {
+                wide_blocks.push(BasicBlockWide::from_bytes(entry.try_into().unwrap()))
+            }
+        }
+        Ok(())
+    })?;
+    Ok((blocks, wide_blocks))
+}
 
+/// Reads `count` binary records of `record_size` bytes, passing each to `f`.
+fn for_each_bb_record(
+    reader: &mut impl Read,
+    count: usize,
+    record_size: usize,
+    mut f: impl FnMut(&[u8]) -> Result<()>,
+) -> Result<()> {
     if count == 0 {
-        return Ok(Vec::new());
+        return Ok(());
     }
 
-    let mut binary_data = vec![0u8; count * consts::BB_ENTRY_SIZE];
-    reader.read_exact(&mut binary_data)?;
+    // The count comes from the file, so read in bounded chunks rather than
+    // allocating for it up front.
+    let chunk_records = count.min(consts::BB_CHUNK_RECORDS);
+    let mut chunk = vec![0u8; chunk_records * record_size];
+    let mut remaining = count;
+    while remaining > 0 {
+        let records = remaining.min(chunk_records);
+        let buf = &mut chunk[..records * record_size];
+        let filled = read_up_to(reader, buf)?;
+        if filled < buf.len() {
+            let complete = count - remaining + filled / record_size;
+            return Err(truncated_bb_table_error(complete, count));
+        }
+        for entry in buf.chunks_exact(record_size) {
+            f(entry)?;
+        }
+        remaining -= records;
+    }
 
-    let blocks = binary_data
-        .chunks_exact(consts::BB_ENTRY_SIZE)
-        .map(|chunk| BasicBlock {
-            start: u32::from_le_bytes(chunk[0..4].try_into().unwrap()),
-            size: u16::from_le_bytes(chunk[4..6].try_into().unwrap()),
-            module_id: u16::from_le_bytes(chunk[6..8].try_into().unwrap()),
-        })
-        .collect();
+    Ok(())
+}
+
+/// The error for a BB table that ends after `complete` of `count` records.
+fn truncated_bb_table_error(complete: usize, count: usize) -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!("Read {complete} of {count} basic blocks before EOF"),
+    ))
+}
 
-    Ok(blocks)
+/// Reads until `buf` is full or EOF, returning the number of bytes read.
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
 }
 
 /// Writes coverage data to a file path.
+///
+/// I/O errors are reported as `Error::IoWithPath` naming the file.
 pub fn to_file<P: AsRef<Path>>(data: &CoverageData, path: P) -> Result<()> {
-    to_writer(data, &mut File::create(path)?)
+    let path = path.as_ref();
+    let write = || -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        to_writer(data, &mut writer)?;
+        writer.flush()?;
+        Ok(())
+    };
+    write().map_err(|e| e.with_path(path))
+}
+
+/// Formatting of the hex columns of the module table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AddressWidth {
+    /// Addresses zero-padded to 16 hex digits, e.g. `0x0000000000400000`,
+    /// and `checksum`/`timestamp` to 8.
+    #[default]
+    Padded16,
+    /// No zero-padding anywhere, e.g. `0x400000`. Files written this way by
+    /// other tools round-trip byte for byte.
+    Minimal,
+}
+
+/// Options controlling how coverage data is written.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WriteOptions {
+    /// Always emit the `checksum` and `timestamp` columns for versioned module
+    /// tables, writing zero for modules that lack them.
+    pub force_windows_columns: bool,
+    /// How module addresses are formatted.
+    pub address_width: AddressWidth,
+    /// Leave out the `BB Table:` line entirely when there are no blocks,
+    /// for consumers that reject `BB Table: 0 bbs`.
+    pub omit_empty_bb_table: bool,
+    /// Module table column order for versioned tables, e.g. with `path`
+    /// first for consumers that expect it. Must name exactly the columns the
+    /// writer would otherwise emit; `None` uses the canonical order. Ignored
+    /// for legacy tables.
+    pub column_order: Option<Vec<String>>,
 }
 
 /// Writes coverage data to any writer.
 pub fn to_writer<W: Write>(data: &CoverageData, writer: &mut W) -> Result<()> {
+    to_writer_with_options(data, writer, &WriteOptions::default())
+}
+
+/// Writes coverage data to any writer using the given options.
+pub fn to_writer_with_options<W: Write>(
+    data: &CoverageData,
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<()> {
+    data.validate()?;
+    write_text_header(data, writer, options)?;
+    write_bb_records(
+        data,
+        writer,
+        options,
+        &data.basic_blocks,
+        BasicBlock::to_bytes,
+    )
+}
+
+/// Writes the header and module table of `data` followed by a BB table of
+/// `blocks` in the non-standard 16-byte [`BasicBlockWide`] record layout.
+///
+/// The blocks held in `data` are not written. Fails with
+/// `Error::ValidationError` before writing anything if a block references a
+/// module that does not exist.
+pub fn to_writer_wide<W: Write>(
+    data: &CoverageData,
+    blocks: &[BasicBlockWide],
+    writer: &mut W,
+    options: &WriteOptions,
+) -> Result<()> {
+    data.validate()?;
+    check_wide_block_refs(data, blocks)?;
+    write_text_header(data, writer, options)?;
+    write_bb_records(data, writer, options, blocks, BasicBlockWide::to_bytes)
+}
+
+/// Checks that every wide block references a module of `data`.
+fn check_wide_block_refs(data: &CoverageData, blocks: &[BasicBlockWide]) -> Result<()> {
+    match blocks
+        .iter()
+        .find(|bb| bb.module_id as usize >= data.modules.len())
+    {
+        Some(bb) => Err(Error::ValidationError(format!(
+            "Basic block references invalid module ID: {}",
+            bb.module_id
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Writes the `BB Table:` line followed by the encoded `blocks`.
+fn write_bb_records<T, const N: usize>(
+    data: &CoverageData,
+    writer: &mut impl Write,
+    options: &WriteOptions,
+    blocks: &[T],
+    encode: impl Fn(&T) -> [u8; N],
+) -> Result<()> {
+    let suffix = data
+        .bb_table_suffix
+        .as_deref()
+        .unwrap_or(consts::BB_TABLE_SUFFIX);
+    if blocks.is_empty() && options.omit_empty_bb_table {
+        return Ok(());
+    }
+    write!(writer, "{}{}", consts::BB_TABLE_PREFIX, blocks.len())?;
+    if suffix.is_empty() {
+        writeln!(writer)?;
+    } else {
+        writeln!(writer, " {suffix}")?;
+    }
+    // Encode in bounded batches so memory stays constant for huge tables
+    let mut binary_data = Vec::with_capacity(blocks.len().min(consts::BB_CHUNK_RECORDS) * N);
+    for batch in blocks.chunks(consts::BB_CHUNK_RECORDS) {
+        binary_data.clear();
+        for bb in batch {
+            binary_data.extend_from_slice(&encode(bb));
+        }
+        writer.write_all(&binary_data)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the header and module table of `data` followed by a BB table made
+/// of pre-encoded 8-byte records, copied to the output without decoding.
+///
+/// The blocks held in `data` are not written. Fails with
+/// `Error::ValidationError` before writing anything if `raw_bb` is not a
+/// whole number of records or references a module that does not exist.
+pub fn to_writer_with_raw_bb<W: Write>(
+    data: &CoverageData,
+    writer: &mut W,
+    raw_bb: &[u8],
+) -> Result<()> {
     data.validate()?;
+    data.check_raw_bb_bytes(raw_bb)?;
+    write_text_header(data, writer, &WriteOptions::default())?;
+    let suffix = data
+        .bb_table_suffix
+        .as_deref()
+        .unwrap_or(consts::BB_TABLE_SUFFIX);
+    write!(
+        writer,
+        "{}{}",
+        consts::BB_TABLE_PREFIX,
+        raw_bb.len() / consts::BB_ENTRY_SIZE
+    )?;
+    if suffix.is_empty() {
+        writeln!(writer)?;
+    } else {
+        writeln!(writer, " {suffix}")?;
+    }
+    writer.write_all(raw_bb)?;
+    Ok(())
+}
+
+/// Decodes whole 8-byte BB records, ignoring any trailing partial record.
+fn decode_raw_bb_bytes(bytes: &[u8]) -> impl Iterator<Item = BasicBlock> + '_ {
+    bytes
+        .chunks_exact(consts::BB_ENTRY_SIZE)
+        .map(|entry| BasicBlock::from_bytes(entry.try_into().unwrap()))
+}
+
+/// Writes the version and flavor lines and the module table.
+fn write_text_header(
+    data: &CoverageData,
+    writer: &mut impl Write,
+    options: &WriteOptions,
+) -> Result<()> {
+    // Columns are decided once for the whole table so every row matches the
+    // Columns header, and checked before anything is written.
+    let columns = module_columns(data, options)?;
 
     // Write header
     writeln!(writer, "{}{}", consts::VERSION_PREFIX, data.header.version)?;
-    writeln!(writer, "{}{}", consts::FLAVOR_PREFIX, data.header.flavor)?;
+    write!(writer, "{}{}", consts::FLAVOR_PREFIX, data.header.flavor)?;
+    if let Some(metadata) = &data.header.flavor_metadata {
+        write!(writer, "\t{metadata}")?;
+    }
+    writeln!(writer)?;
+    for line in &data.extra_headers {
+        writeln!(writer, "{line}")?;
+    }
 
     // Write module table
     if data.module_version == ModuleTableVersion::Legacy {
@@ -552,101 +3026,192 @@ pub fn to_writer<W: Write>(data: &CoverageData, writer: &mut W) -> Result<()> {
             data.module_version as u32,
             data.modules.len()
         )?;
-
-        let has_windows_fields = data
-            .modules
-            .iter()
-            .any(|m| m.checksum.is_some() || m.timestamp.is_some());
-        let columns = match data.module_version {
-            ModuleTableVersion::Legacy => "id, base, end, entry, path", // Should be unreachable
-            ModuleTableVersion::V2 => {
-                if has_windows_fields {
-                    "id, base, end, entry, checksum, timestamp, path"
-                } else {
-                    "id, base, end, entry, path"
-                }
-            }
-            ModuleTableVersion::V3 => {
-                if has_windows_fields {
-                    "id, containing_id, start, end, entry, checksum, timestamp, path"
-                } else {
-                    "id, containing_id, start, end, entry, path"
-                }
-            }
-            ModuleTableVersion::V4 => {
-                if has_windows_fields {
-                    "id, containing_id, start, end, entry, offset, checksum, timestamp, path"
-                } else {
-                    "id, containing_id, start, end, entry, offset, path"
-                }
-            }
-        };
-        writeln!(writer, "{}{}", consts::COLUMNS_PREFIX, columns)?;
+        writeln!(writer, "{}{}", consts::COLUMNS_PREFIX, columns.join(", "))?;
     }
 
     for module in &data.modules {
-        write_module_line(writer, module, data.module_version)?;
+        write_module_line(writer, module, &columns, options)?;
     }
 
-    // Write basic block table
-    writeln!(
-        writer,
-        "{} {} bbs",
-        consts::BB_TABLE_PREFIX,
-        data.basic_blocks.len()
-    )?;
-    if !data.basic_blocks.is_empty() {
-        let mut binary_data = Vec::with_capacity(data.basic_blocks.len() * consts::BB_ENTRY_SIZE);
-        for bb in &data.basic_blocks {
-            binary_data.extend_from_slice(&bb.start.to_le_bytes());
-            binary_data.extend_from_slice(&bb.size.to_le_bytes());
-            binary_data.extend_from_slice(&bb.module_id.to_le_bytes());
-        }
-        writer.write_all(&binary_data)?;
+    Ok(())
+}
+
+/// Returns the module table columns to write, in order.
+///
+/// This is the canonical order for the table version unless
+/// `options.column_order` gives another, which must name exactly the same
+/// columns.
+fn module_columns<'a>(data: &CoverageData, options: &'a WriteOptions) -> Result<Vec<&'a str>> {
+    let version = data.module_version;
+    if version == ModuleTableVersion::Legacy {
+        return Ok(vec!["id", "base", "end", "entry", "path"]);
     }
 
-    Ok(())
+    let mut canonical = vec!["id"];
+    if version >= ModuleTableVersion::V3 {
+        canonical.extend(["containing_id", "start"]);
+    } else {
+        canonical.push("base");
+    }
+    canonical.push("end");
+    if data.uses_entry_column() {
+        canonical.push("entry");
+    }
+    if version >= ModuleTableVersion::V4 {
+        canonical.push("offset");
+    }
+    if data.uses_windows_columns(options) {
+        canonical.extend(["checksum", "timestamp"]);
+    }
+    canonical.push("path");
+
+    let Some(order) = &options.column_order else {
+        return Ok(canonical);
+    };
+    let mut sorted: Vec<&str> = order.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    let mut expected = canonical.clone();
+    expected.sort_unstable();
+    if sorted != expected {
+        return Err(Error::ValidationError(format!(
+            "Column order must list exactly these columns: {}",
+            canonical.join(", ")
+        )));
+    }
+    // Readers split rows on commas, giving any excess to the last column
+    if order.last().is_some_and(|last| last != "path")
+        && data.modules.iter().any(|m| m.path.contains(','))
+    {
+        return Err(Error::ValidationError(
+            "path must be the last column when a module path contains a comma".to_string(),
+        ));
+    }
+    Ok(order.iter().map(String::as_str).collect())
 }
 
 fn write_module_line(
     writer: &mut impl Write,
     module: &ModuleEntry,
-    version: ModuleTableVersion,
+    columns: &[&str],
+    options: &WriteOptions,
 ) -> Result<()> {
-    let mut parts = vec![module.id.to_string()];
-    let has_windows_fields = module.checksum.is_some() || module.timestamp.is_some();
+    let format_address = |addr: u64| match options.address_width {
+        AddressWidth::Padded16 => format!("0x{addr:016x}"),
+        AddressWidth::Minimal => format!("0x{addr:x}"),
+    };
+    let format_u32 = |value: u32| match options.address_width {
+        AddressWidth::Padded16 => format!("0x{value:08x}"),
+        AddressWidth::Minimal => format!("0x{value:x}"),
+    };
 
-    if version >= ModuleTableVersion::V3 {
-        parts.push(
-            module
+    let parts: Vec<String> = columns
+        .iter()
+        .map(|&column| match column {
+            "id" => module.id.to_string(),
+            "containing_id" => module
                 .containing_id
                 .map_or_else(|| "-1".to_string(), |id| id.to_string()),
-        );
-    }
-
-    parts.push(format!("0x{:016x}", module.base));
-    parts.push(format!("0x{:016x}", module.end));
-    parts.push(format!("0x{:016x}", module.entry));
+            "base" | "start" => format_address(module.base),
+            "end" => format_address(module.end),
+            "entry" => format_address(module.entry.unwrap_or(0)),
+            "offset" => format!("0x{:x}", module.offset.unwrap_or(0)),
+            "checksum" => format_u32(module.checksum.unwrap_or(0)),
+            "timestamp" => format_u32(module.timestamp.unwrap_or(0)),
+            "path" => module.path.clone(),
+            _ => unreachable!("unknown module column {column}"),
+        })
+        .collect();
 
-    if version >= ModuleTableVersion::V4 {
-        parts.push(format!("0x{:x}", module.offset.unwrap_or(0)));
-    }
+    writeln!(writer, "{}", parts.join(", "))?;
+    Ok(())
+}
 
-    let use_windows_cols = match version {
-        ModuleTableVersion::V2 | ModuleTableVersion::V3 | ModuleTableVersion::V4 => {
-            has_windows_fields
-        }
-        _ => false,
+/// Writes `data` with `to_writer`, parses it back with `from_reader`
+/// (keeping extra header lines) and checks that nothing was lost.
+///
+/// Returns `Error::ValidationError` naming the first field that differs.
+/// Optional module fields are compared using the values the writer emits when
+/// they are absent (`-1` for `containing_id`, `0` otherwise).
+pub fn verify_roundtrip(data: &CoverageData) -> Result<()> {
+    let mut buffer = Vec::new();
+    to_writer(data, &mut buffer)?;
+    let options = ParseOptions {
+        preserve_extra_headers: true,
+        ..Default::default()
     };
-
-    if use_windows_cols {
-        parts.push(format!("0x{:08x}", module.checksum.unwrap_or(0)));
-        parts.push(format!("0x{:08x}", module.timestamp.unwrap_or(0)));
+    let parsed = from_reader_with_options(buffer.as_slice(), &options)?;
+
+    fn check<T: PartialEq + fmt::Debug>(field: &str, expected: T, found: T) -> Result<()> {
+        if expected == found {
+            Ok(())
+        } else {
+            Err(Error::ValidationError(format!(
+                "Roundtrip mismatch in {field}: expected {expected:?}, found {found:?}"
+            )))
+        }
     }
 
-    parts.push(module.path.clone());
-
-    writeln!(writer, "{}", parts.join(", "))?;
+    check("header version", data.header.version, parsed.header.version)?;
+    check("header flavor", &data.header.flavor, &parsed.header.flavor)?;
+    check(
+        "header flavor metadata",
+        &data.header.flavor_metadata,
+        &parsed.header.flavor_metadata,
+    )?;
+    check("extra headers", &data.extra_headers, &parsed.extra_headers)?;
+    check("module version", data.module_version, parsed.module_version)?;
+    check("module count", data.modules.len(), parsed.modules.len())?;
+    for (i, (a, b)) in data.modules.iter().zip(&parsed.modules).enumerate() {
+        check(&format!("module {i} id"), a.id, b.id)?;
+        check(&format!("module {i} path"), &a.path, &b.path)?;
+        check(&format!("module {i} base"), a.base, b.base)?;
+        check(&format!("module {i} end"), a.end, b.end)?;
+        check(
+            &format!("module {i} entry"),
+            a.entry.unwrap_or(0),
+            b.entry.unwrap_or(0),
+        )?;
+        check(
+            &format!("module {i} containing_id"),
+            a.containing_id.unwrap_or(-1),
+            b.containing_id.unwrap_or(-1),
+        )?;
+        check(
+            &format!("module {i} offset"),
+            a.offset.unwrap_or(0),
+            b.offset.unwrap_or(0),
+        )?;
+        check(
+            &format!("module {i} checksum"),
+            a.checksum.unwrap_or(0),
+            b.checksum.unwrap_or(0),
+        )?;
+        check(
+            &format!("module {i} timestamp"),
+            a.timestamp.unwrap_or(0),
+            b.timestamp.unwrap_or(0),
+        )?;
+    }
+    check(
+        "block count",
+        data.basic_blocks.len(),
+        parsed.basic_blocks.len(),
+    )?;
+    for (i, (a, b)) in data
+        .basic_blocks
+        .iter()
+        .zip(&parsed.basic_blocks)
+        .enumerate()
+    {
+        check(&format!("block {i} start"), a.start, b.start)?;
+        check(&format!("block {i} size"), a.size, b.size)?;
+        check(&format!("block {i} module_id"), a.module_id, b.module_id)?;
+    }
+    check(
+        "BB table suffix",
+        &data.bb_table_suffix,
+        &parsed.bb_table_suffix,
+    )?;
     Ok(())
 }
 
@@ -670,6 +3235,30 @@ mod tests {
         assert_eq!(version_err.to_string(), "Unsupported drcov version: 3");
     }
 
+    #[test]
+    fn test_error_kind() {
+        let cases = [
+            (Error::Io(std::io::Error::other("io")), ErrorKind::Io),
+            (Error::InvalidFormat("x".into()), ErrorKind::InvalidFormat),
+            (Error::UnsupportedVersion(3), ErrorKind::UnsupportedVersion),
+            (
+                Error::InvalidModuleTable("x".into()),
+                ErrorKind::InvalidModuleTable,
+            ),
+            (Error::InvalidBbTable("x".into()), ErrorKind::InvalidBbTable),
+            (
+                Error::ValidationError("x".into()),
+                ErrorKind::ValidationError,
+            ),
+        ];
+        for (err, kind) in cases {
+            assert_eq!(err.kind(), kind);
+        }
+
+        let err = from_reader(Cursor::new("DRCOV VERSION: 3\n")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnsupportedVersion);
+    }
+
     #[test]
     fn test_file_header_default() {
         let header = FileHeader::default();
@@ -683,7 +3272,7 @@ mod tests {
             id: 0,
             base: 0x400000,
             end: 0x450000,
-            entry: 0x401000,
+            entry: Some(0x401000),
             path: "/bin/test".to_string(),
             ..Default::default()
         };
@@ -700,7 +3289,7 @@ mod tests {
             id: 0,
             base: 0x400000,
             end: 0x450000,
-            entry: 0x401000,
+            entry: Some(0x401000),
             path: "/bin/test".to_string(),
             ..Default::default()
         };
@@ -714,6 +3303,83 @@ mod tests {
         assert_eq!(bb.absolute_address(&module), 0x401000);
     }
 
+    #[test]
+    fn test_module_table_version_from_str() {
+        let cases = [
+            ("legacy", ModuleTableVersion::Legacy),
+            ("LEGACY", ModuleTableVersion::Legacy),
+            ("2", ModuleTableVersion::V2),
+            ("v2", ModuleTableVersion::V2),
+            ("3", ModuleTableVersion::V3),
+            ("V3", ModuleTableVersion::V3),
+            ("4", ModuleTableVersion::V4),
+            ("v4", ModuleTableVersion::V4),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(input.parse::<ModuleTableVersion>().unwrap(), expected);
+        }
+
+        assert!(matches!(
+            "v5".parse::<ModuleTableVersion>(),
+            Err(Error::InvalidModuleTable(_))
+        ));
+    }
+
+    #[test]
+    fn test_module_table_version_display() {
+        assert_eq!(ModuleTableVersion::Legacy.to_string(), "legacy");
+        assert_eq!(ModuleTableVersion::V2.to_string(), "v2");
+        assert_eq!(ModuleTableVersion::V3.to_string(), "v3");
+        assert_eq!(ModuleTableVersion::V4.to_string(), "v4");
+
+        for version in [
+            ModuleTableVersion::Legacy,
+            ModuleTableVersion::V2,
+            ModuleTableVersion::V3,
+            ModuleTableVersion::V4,
+        ] {
+            assert_eq!(
+                version.to_string().parse::<ModuleTableVersion>().unwrap(),
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn test_basic_block_bytes() {
+        let bb = BasicBlock {
+            start: 0x12345678,
+            size: 0xabcd,
+            module_id: 0x0102,
+        };
+        let bytes = bb.to_bytes();
+        assert_eq!(bytes, [0x78, 0x56, 0x34, 0x12, 0xcd, 0xab, 0x02, 0x01]);
+        assert_eq!(BasicBlock::from_bytes(&bytes), bb);
+    }
+
+    #[test]
+    fn test_basic_blocks_to_bytes_matches_writer() {
+        let coverage = CoverageData::builder()
+            .add_module("/bin/test", 0x400000, 0x450000)
+            .add_coverage(0, 0x1000, 32)
+            .add_coverage(0, 0x2000, 16)
+            .build()
+            .unwrap();
+
+        let bytes = coverage.basic_blocks_to_bytes();
+        assert_eq!(bytes.len(), 16);
+
+        let mut buffer = Vec::new();
+        to_writer(&coverage, &mut buffer).unwrap();
+        assert!(buffer.ends_with(&bytes));
+
+        let decoded: Vec<_> = bytes
+            .chunks_exact(8)
+            .map(|chunk| BasicBlock::from_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(decoded, coverage.basic_blocks);
+    }
+
     #[test]
     fn test_coverage_builder() {
         let coverage = CoverageData::builder()
@@ -735,6 +3401,30 @@ mod tests {
         assert_eq!(coverage.modules[1].path, "/lib/libc.so");
     }
 
+    #[test]
+    fn test_coverage_counts() {
+        let empty = CoverageData::default();
+        assert!(empty.is_empty());
+        assert_eq!(empty.module_count(), 0);
+        assert_eq!(empty.block_count(), 0);
+
+        let modules_only = CoverageData::builder()
+            .add_module("/bin/test", 0x400000, 0x450000)
+            .build()
+            .unwrap();
+        assert!(!modules_only.is_empty());
+        assert_eq!(modules_only.module_count(), 1);
+        assert_eq!(modules_only.block_count(), 0);
+
+        let coverage = CoverageData::builder()
+            .add_module("/bin/test", 0x400000, 0x450000)
+            .add_coverage(0, 0x1000, 32)
+            .add_coverage(0, 0x1000, 32)
+            .build()
+            .unwrap();
+        assert_eq!(coverage.block_count(), 2);
+    }
+
     #[test]
     fn test_coverage_validation() {
         // Test non-sequential module IDs
@@ -795,6 +3485,123 @@ mod tests {
         assert_eq!(stats.get(&2), None);
     }
 
+    #[test]
+    fn test_coverage_stats_by_path() {
+        let coverage = CoverageData::builder()
+            .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+            .add_module("/bin/test", 0x400000, 0x450000)
+            .add_module("/lib/libc.so", 0x7ffe00000000, 0x7ffe00100000)
+            .add_module("/lib/unused.so", 0x500000, 0x600000)
+            .add_coverage(0, 0x1000, 32)
+            .add_coverage(1, 0x2000, 16)
+            .add_coverage(2, 0x3000, 8)
+            .add_coverage(2, 0x4000, 8)
+            .build()
+            .unwrap();
+
+        let stats = coverage.coverage_stats_by_path();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats.get("/lib/libc.so"), Some(&3));
+        assert_eq!(stats.get("/bin/test"), Some(&1));
+        assert_eq!(stats.get("/lib/unused.so"), None);
+    }
+
+    #[test]
+    fn test_unique_covered_bytes() {
+        let coverage = CoverageData::builder()
+            .add_module("/bin/test", 0x400000, 0x450000)
+            .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+            .add_coverage(0, 0x0, 0x20)
+            .add_coverage(0, 0x10, 0x20)
+            .add_coverage(1, 0x0, 0x10)
+            .build()
+            .unwrap();
+
+        assert_eq!(coverage.total_covered_bytes(), 0x50);
+        // The overlap in module 0 is counted once; module 1 is separate
+        assert_eq!(coverage.unique_covered_bytes(), 0x30 + 0x10);
+    }
+
+    #[test]
+    fn test_covered_module_count() {
+        let coverage = CoverageData::builder()
+            .add_module("/bin/a", 0x400000, 0x500000)
+            .add_module("/lib/unused.so", 0x600000, 0x700000)
+            .add_module("/lib/b.so", 0x7f0000, 0x800000)
+            .add_module("/lib/unused2.so", 0x900000, 0xa00000)
+            .add_coverage(0, 0x1000, 32)
+            .add_coverage(2, 0x2000, 16)
+            .add_coverage(2, 0x3000, 16)
+            .build()
+            .unwrap();
+        assert_eq!(coverage.covered_module_count(), 2);
+
+        let empty = CoverageData::builder()
+            .add_module("/bin/a", 0x400000, 0x500000)
+            .build()
+            .unwrap();
+        assert_eq!(empty.covered_module_count(), 0);
+    }
+
+    #[test]
+    fn test_coverage_stats_struct() {
+        let coverage = CoverageData::builder()
+            .add_module("/bin/a", 0x400000, 0x500000)
+            .add_module("/lib/b.so", 0x7f0000, 0x800000)
+            .add_module("/lib/unused.so", 0x900000, 0xa00000)
+            .add_coverage(0, 0x1000, 32)
+            .add_coverage(0, 0x1010, 32)
+            .add_coverage(1, 0x2000, 16)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            coverage.stats(),
+            CoverageStats {
+                module_count: 3,
+                block_count: 3,
+                total_bytes: 80,
+                unique_bytes: 64,
+                covered_modules: 2,
+                uncovered_modules: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_coverage_data_display() {
+        let coverage = CoverageData::builder()
+            .flavor("foo")
+            .module_version(ModuleTableVersion::V4)
+            .add_module("/bin/a", 0x400000, 0x500000)
+            .add_module("/lib/b.so", 0x7f0000, 0x800000)
+            .add_coverage(0, 0x1000, 32)
+            .add_coverage(1, 0x2000, 16)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            coverage.to_string(),
+            "drcov v2 (flavor=foo, v4): 2 modules, 2 blocks, 48 bytes"
+        );
+    }
+
+    #[test]
+    fn test_estimated_instruction_count() {
+        let coverage = CoverageData::builder()
+            .add_module("/bin/arm64", 0x400000, 0x450000)
+            .add_coverage(0, 0x0, 16)
+            .add_coverage(0, 0x8, 16)
+            .add_coverage(0, 0x100, 6)
+            .build()
+            .unwrap();
+
+        // 24 merged bytes + 6 bytes = 30 bytes, i.e. 7 whole instructions
+        assert_eq!(coverage.estimated_instruction_count(4), 7);
+        assert_eq!(coverage.estimated_instruction_count(1), 30);
+        assert_eq!(coverage.estimated_instruction_count(0), 0);
+    }
+
     #[test]
     fn test_parse_simple_drcov() {
         let drcov_content = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x0000000000400000, 0x0000000000450000, 0x0000000000401000, /bin/test\nBB Table: 0 bbs\n";
@@ -899,7 +3706,7 @@ mod tests {
                 id: 0,
                 base: 0x400000,
                 end: 0x450000,
-                entry: 0x401000,
+                entry: Some(0x401000),
                 path: "/bin/test".to_string(),
                 checksum: Some(0x12345678),
                 timestamp: Some(0x87654321),
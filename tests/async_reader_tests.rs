@@ -0,0 +1,84 @@
+#![cfg(feature = "tokio")]
+
+use drcov::{
+    from_async_reader, from_async_reader_with_options, from_reader_with_options, to_writer,
+    CoverageData, Error, ModuleTableVersion, ParseOptions,
+};
+
+#[tokio::test]
+async fn test_async_roundtrip() {
+    let original = CoverageData::builder()
+        .flavor("async_test")
+        .module_version(ModuleTableVersion::V2)
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .build()
+        .unwrap();
+
+    let mut buffer = Vec::new();
+    to_writer(&original, &mut buffer).unwrap();
+
+    let parsed = from_async_reader(&buffer[..]).await.unwrap();
+    assert_eq!(parsed, original);
+}
+
+#[tokio::test]
+async fn test_async_truncated_bb_table() {
+    let mut data = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 2 bbs\n".to_vec();
+    data.extend_from_slice(&[0u8; 8]);
+
+    assert!(matches!(
+        from_async_reader(&data[..]).await,
+        Err(Error::Io(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_async_matches_sync_with_options() {
+    let mut commented = b"# captured by hand\nDRCOV VERSION: 2\n\ndrcov flavor: test\nDRCOV COMMENT: nightly\nModule Table: version 2, count 1\nColumns: id, base, end, path\n0, 0x400000, 0x500000, /bin/test\n# blocks follow\nbb table: 2 bbs\n".to_vec();
+    // Records containing newline bytes must not be read as text
+    commented.extend_from_slice(&[0x0a, 0x0a, 0, 0, 8, 0, 0, 0]);
+    commented.extend_from_slice(&[0x0a, 0x00, 0, 0, 0x0a, 0, 0, 0]);
+    let mut trailing = commented.clone();
+    trailing.extend_from_slice(b"junk");
+
+    let lenient = ParseOptions {
+        allow_comments: true,
+        case_insensitive_headers: true,
+        preserve_extra_headers: true,
+        ..Default::default()
+    };
+    let strict = ParseOptions {
+        reject_trailing_data: true,
+        ..lenient.clone()
+    };
+    let limited = ParseOptions {
+        max_blocks: Some(1),
+        ..lenient.clone()
+    };
+
+    for (data, options) in [
+        (&commented, &lenient),
+        (&trailing, &lenient),
+        (&trailing, &strict),
+        (&commented, &limited),
+        (&commented, &ParseOptions::default()),
+    ] {
+        let sync = from_reader_with_options(&data[..], options);
+        let parsed = from_async_reader_with_options(&data[..], options).await;
+        match (sync, parsed) {
+            (Ok(sync), Ok(parsed)) => assert_eq!(parsed, sync),
+            (Err(sync), Err(parsed)) => assert_eq!(parsed.to_string(), sync.to_string()),
+            (sync, parsed) => panic!("sync {sync:?} but async {parsed:?}"),
+        }
+    }
+
+    let parsed = from_async_reader_with_options(&commented[..], &lenient)
+        .await
+        .unwrap();
+    assert_eq!(parsed.extra_headers, vec!["DRCOV COMMENT: nightly"]);
+    assert_eq!(parsed.basic_blocks[0].start, 0x0a0a);
+    assert_eq!(parsed.basic_blocks[1].size, 0x0a);
+}
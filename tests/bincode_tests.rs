@@ -0,0 +1,53 @@
+#![cfg(feature = "bincode")]
+
+use drcov::{CoverageData, Error, ModuleEntry, ModuleTableVersion};
+
+#[test]
+fn test_bincode_roundtrip_with_all_module_fields() {
+    let mut original = CoverageData::builder()
+        .flavor("cache")
+        .module_version(ModuleTableVersion::V4)
+        .add_full_module(ModuleEntry {
+            id: 0,
+            base: 0x140000000,
+            end: 0x140100000,
+            entry: Some(0x140001000),
+            path: "C:\\app.exe".to_string(),
+            containing_id: Some(0),
+            offset: Some(0x400),
+            checksum: Some(0xdeadbeef),
+            timestamp: Some(0x5f3e1234),
+        })
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .build()
+        .unwrap();
+    original.header.flavor_metadata = Some("build=42".to_string());
+    original.bb_table_suffix = Some(String::new());
+    original.source_columns = Some(vec!["id".to_string(), "path".to_string()]);
+    original.extra_headers = vec!["DRCOV COMMENT: cached".to_string()];
+    original.modules[1].entry = None;
+
+    let bytes = original.to_bincode().unwrap();
+    let decoded = CoverageData::from_bincode(&bytes).unwrap();
+    assert_eq!(decoded, original);
+    assert_eq!(decoded.extra_headers, original.extra_headers);
+    assert_eq!(decoded.modules[1].entry, None);
+    // Equality ignores `source_columns`, so check it separately
+    assert_eq!(decoded.source_columns, original.source_columns);
+}
+
+#[test]
+fn test_bincode_rejects_truncated_input() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .add_coverage(0, 0x1000, 32)
+        .build()
+        .unwrap();
+    let bytes = coverage.to_bincode().unwrap();
+
+    let result = CoverageData::from_bincode(&bytes[..bytes.len() / 2]);
+    assert!(result.is_err());
+    assert!(!matches!(result, Err(Error::ValidationError(_))));
+}
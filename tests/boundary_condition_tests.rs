@@ -10,7 +10,7 @@ fn test_maximum_values() {
             id: 0,
             base: u64::MAX - 1,
             end: u64::MAX,
-            entry: u64::MAX - 1,
+            entry: Some(u64::MAX - 1),
             path: "a".repeat(1000), // Very long path
             containing_id: Some(i32::MAX),
             offset: Some(u64::MAX),
@@ -51,7 +51,7 @@ fn test_minimum_values() {
             id: 0,
             base: 0,
             end: 0,
-            entry: 0,
+            entry: Some(0),
             path: String::new(), // Empty path
             containing_id: Some(i32::MIN),
             offset: Some(0),
@@ -248,7 +248,7 @@ fn test_negative_containing_id_boundaries() {
                 id: 0,
                 base: 0x400000,
                 end: 0x500000,
-                entry: 0x401000,
+                entry: Some(0x401000),
                 path: format!("/negative_{i}"),
                 containing_id: Some(containing_id),
                 ..Default::default()
@@ -285,7 +285,7 @@ fn test_hex_value_boundaries() {
         let coverage = from_reader(Cursor::new(drcov_content)).unwrap();
         assert_eq!(coverage.modules[0].base, expected);
         assert_eq!(coverage.modules[0].end, expected);
-        assert_eq!(coverage.modules[0].entry, expected);
+        assert_eq!(coverage.modules[0].entry, Some(expected));
     }
 }
 
@@ -418,3 +418,33 @@ fn test_flavor_string_boundaries() {
         );
     }
 }
+
+#[test]
+fn test_huge_declared_block_count() {
+    let header = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 4294967295 bbs\n";
+    let mut data = Vec::new();
+    data.extend_from_slice(header.as_bytes());
+    data.extend_from_slice(&0x1000u32.to_le_bytes());
+    data.extend_from_slice(&32u16.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+
+    // Fails cleanly on the short body instead of allocating ~32 GiB
+    assert!(matches!(
+        from_reader(Cursor::new(data)),
+        Err(drcov::Error::Io(_))
+    ));
+}
+
+#[test]
+fn test_block_count_spanning_read_chunks() {
+    let mut builder = CoverageData::builder().add_module("/bin/test", 0x400000, 0x500000);
+    for i in 0..20_000u32 {
+        builder = builder.add_coverage(0, i * 4, 4);
+    }
+    let coverage = builder.build().unwrap();
+
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+    let parsed = from_reader(Cursor::new(buffer)).unwrap();
+    assert_eq!(parsed.basic_blocks, coverage.basic_blocks);
+}
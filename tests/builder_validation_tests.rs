@@ -1,4 +1,4 @@
-use drcov::{BasicBlock, CoverageData, ModuleEntry, ModuleTableVersion};
+use drcov::{BasicBlock, CoverageData, Error, ModuleEntry, ModuleTableVersion};
 
 #[test]
 fn test_builder_sequential_module_ids() {
@@ -245,7 +245,7 @@ fn test_builder_module_entry_fields() {
             id: 0,
             base: 0x400000,
             end: 0x500000,
-            entry: 0x401000,
+            entry: Some(0x401000),
             path: "/bin/test".to_string(),
             containing_id: Some(42),
             offset: Some(0x1000),
@@ -261,7 +261,7 @@ fn test_builder_module_entry_fields() {
             id: 0,
             base: 0x400000,
             end: 0x500000,
-            entry: 0x401000,
+            entry: Some(0x401000),
             path: "/bin/test".to_string(),
             containing_id: Some(-1),
             ..Default::default()
@@ -321,3 +321,187 @@ fn test_builder_validation_edge_cases() {
         .build();
     assert!(max_module_id.is_ok());
 }
+
+#[test]
+fn test_builder_reject_inverted_ranges() {
+    // Inverted ranges are permitted by default
+    let permissive = CoverageData::builder()
+        .add_module("/bin/test", 0x500000, 0x400000)
+        .build();
+    assert!(permissive.is_ok());
+
+    let strict = CoverageData::builder()
+        .reject_inverted_ranges()
+        .add_module("/bin/test", 0x500000, 0x400000)
+        .build();
+    assert!(matches!(strict, Err(Error::ValidationError(_))));
+
+    // Empty ranges are not inverted
+    let empty = CoverageData::builder()
+        .reject_inverted_ranges()
+        .add_module("/bin/test", 0x400000, 0x400000)
+        .build();
+    assert!(empty.is_ok());
+}
+
+#[test]
+fn test_builder_require_non_empty_paths() {
+    // Empty paths are permitted by default
+    let permissive = CoverageData::builder()
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .add_module("", 0x500000, 0x600000)
+        .build();
+    assert!(permissive.is_ok());
+
+    let strict = CoverageData::builder()
+        .require_non_empty_paths()
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .add_module("", 0x500000, 0x600000)
+        .build();
+    match strict {
+        Err(Error::ValidationError(msg)) => assert_eq!(msg, "Module 1 has an empty path"),
+        other => panic!("expected empty path error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_builder_bulk_add() {
+    let modules = (0..4u32).map(|i| ModuleEntry {
+        id: i,
+        base: 0x400000 + i as u64 * 0x100000,
+        end: 0x480000 + i as u64 * 0x100000,
+        path: format!("/lib/module_{i}.so"),
+        ..Default::default()
+    });
+    let blocks = (0..1000u32).map(|i| BasicBlock {
+        start: i * 0x10,
+        size: 16,
+        module_id: (i % 4) as u16,
+    });
+
+    let coverage = CoverageData::builder()
+        .add_modules(modules)
+        .add_blocks(blocks)
+        .build()
+        .unwrap();
+    assert_eq!(coverage.modules.len(), 4);
+    assert_eq!(coverage.basic_blocks.len(), 1000);
+    assert_eq!(coverage.basic_blocks[999].start, 999 * 0x10);
+
+    // Module IDs are still validated at build time
+    let invalid = CoverageData::builder()
+        .add_modules([ModuleEntry {
+            id: 3,
+            ..Default::default()
+        }])
+        .build();
+    assert!(matches!(invalid, Err(Error::ValidationError(_))));
+}
+
+#[test]
+fn test_builder_module_count_limit() {
+    let modules = |count: u32| {
+        (0..count).map(|i| ModuleEntry {
+            id: i,
+            ..Default::default()
+        })
+    };
+
+    // 65536 modules are all addressable by a u16 module_id
+    assert!(CoverageData::builder()
+        .add_modules(modules(65536))
+        .build()
+        .is_ok());
+
+    match CoverageData::builder().add_modules(modules(65537)).build() {
+        Err(Error::ValidationError(msg)) => assert!(msg.contains("65537")),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn test_builder_try_add_coverage() {
+    let builder = CoverageData::builder()
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .try_add_coverage(0, 0x1000, 32)
+        .unwrap();
+
+    // Module 1 has not been added yet, so this fails before build()
+    let result = builder.try_add_coverage(1, 0x2000, 16);
+    assert!(matches!(result, Err(Error::ValidationError(_))));
+
+    let coverage = CoverageData::builder()
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .add_module("/lib/test", 0x500000, 0x600000)
+        .try_add_coverage(1, 0x2000, 16)
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(coverage.basic_blocks[0].module_id, 1);
+}
+
+#[test]
+fn test_validate_windows_fields() {
+    let windows_module = |id: u32, path: &str, checksum, timestamp| ModuleEntry {
+        id,
+        base: 0x10000000 * (id as u64 + 1),
+        end: 0x10000000 * (id as u64 + 1) + 0x1000,
+        path: path.to_string(),
+        checksum,
+        timestamp,
+        ..Default::default()
+    };
+    let mixed = CoverageData::builder()
+        .module_version(ModuleTableVersion::V2)
+        .add_full_module(windows_module(0, "C:\\app.exe", Some(0x1234), Some(0x5678)))
+        .add_full_module(windows_module(1, "C:\\a.dll", None, None))
+        .add_full_module(windows_module(2, "C:\\b.dll", Some(0x9abc), None))
+        .add_full_module(windows_module(3, "C:\\c.dll", Some(0x1), Some(0x2)))
+        .build()
+        .unwrap();
+    assert_eq!(mixed.validate_windows_fields(), vec![1, 2]);
+
+    // No module has the fields, so no Windows columns are written
+    let linux = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .build()
+        .unwrap();
+    assert!(linux.validate_windows_fields().is_empty());
+}
+
+#[test]
+fn test_module_entry_builder() {
+    let built = ModuleEntry::builder("C:\\app.exe", 0x140000000, 0x140100000)
+        .id(1)
+        .entry(0x140001000)
+        .containing_id(1)
+        .offset(0x400)
+        .checksum(0xdeadbeef)
+        .timestamp(0x5f3e1234)
+        .build();
+
+    assert_eq!(
+        built,
+        ModuleEntry {
+            id: 1,
+            base: 0x140000000,
+            end: 0x140100000,
+            entry: Some(0x140001000),
+            path: "C:\\app.exe".to_string(),
+            containing_id: Some(1),
+            offset: Some(0x400),
+            checksum: Some(0xdeadbeef),
+            timestamp: Some(0x5f3e1234),
+        }
+    );
+
+    let minimal = ModuleEntry::builder("/bin/test", 0x400000, 0x500000).build();
+    assert_eq!(minimal.id, 0);
+    assert_eq!(minimal.checksum, None);
+    let coverage = CoverageData::builder()
+        .add_full_module(minimal)
+        .build()
+        .unwrap();
+    assert_eq!(coverage.modules[0].path, "/bin/test");
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "cli")]
+
+use drcov::ModuleTableVersion;
+use std::process::Command;
+use tempfile::NamedTempFile;
+
+#[test]
+fn test_drcov_write_produces_readable_file() {
+    let output = NamedTempFile::new().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_drcov-write"))
+        .arg(output.path())
+        .args(["--flavor", "cli_test", "--module-version", "v3"])
+        .args(["--module", "/bin/program,0x400000,0x500000"])
+        .args(["--module", "/lib/libc.so,0x7fff00000000,0x7fff00100000"])
+        .args(["--block", "0,0x1000,32", "--block", "1,4096,16"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let coverage = drcov::from_file(output.path()).unwrap();
+    assert_eq!(coverage.header.flavor, "cli_test");
+    assert_eq!(coverage.module_version, ModuleTableVersion::V3);
+    assert_eq!(coverage.modules.len(), 2);
+    assert_eq!(coverage.modules[1].path, "/lib/libc.so");
+    assert_eq!(coverage.modules[1].base, 0x7fff00000000);
+    assert_eq!(coverage.basic_blocks.len(), 2);
+    assert_eq!(coverage.basic_blocks[1].module_id, 1);
+    assert_eq!(coverage.basic_blocks[1].start, 0x1000);
+    assert_eq!(coverage.basic_blocks[1].size, 16);
+}
+
+#[test]
+fn test_drcov_write_rejects_invalid_block() {
+    let output = NamedTempFile::new().unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_drcov-write"))
+        .arg(output.path())
+        .args(["--module", "/bin/program,0x400000,0x500000"])
+        .args(["--block", "3,0x1000,32"])
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("Validation error"));
+}
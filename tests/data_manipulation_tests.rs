@@ -0,0 +1,709 @@
+use drcov::{
+    overlap_matrix, BasicBlock, CoverageBuilder, CoverageData, Error, ModuleEntry,
+    ModuleTableVersion,
+};
+use std::collections::HashMap;
+
+#[test]
+fn test_normalize_paths_to_basename() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/usr/lib/libc.so.6", 0x7fff00000000, 0x7fff00100000)
+        .add_module("libc.so.6", 0x7ffe00000000, 0x7ffe00100000)
+        .add_module("C:\\Windows\\System32\\ntdll.dll", 0x10000000, 0x10100000)
+        .build()
+        .unwrap();
+
+    coverage.normalize_paths(drcov::basename);
+
+    assert_eq!(coverage.modules[0].path, "libc.so.6");
+    assert_eq!(coverage.modules[1].path, "libc.so.6");
+    assert_eq!(coverage.modules[2].path, "ntdll.dll");
+
+    // Differently-prefixed modules now share a path
+    assert_eq!(coverage.modules[0].path, coverage.modules[1].path);
+}
+
+#[test]
+fn test_normalize_paths_with_closure() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/BIN/Program", 0x400000, 0x500000)
+        .build()
+        .unwrap();
+
+    coverage.normalize_paths(|p| p.to_lowercase());
+    assert_eq!(coverage.modules[0].path, "/bin/program");
+}
+
+#[test]
+fn test_map_module_paths() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/build/out/bin/app", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7f0000, 0x800000)
+        .add_module("/build/out/lib/libfoo.so", 0x900000, 0xa00000)
+        .build()
+        .unwrap();
+
+    coverage.map_module_paths(|path| {
+        path.strip_prefix("/build/out/")
+            .map(|rest| format!("/install/{rest}"))
+    });
+
+    let paths: Vec<_> = coverage.modules.iter().map(|m| m.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        ["/install/bin/app", "/lib/libc.so", "/install/lib/libfoo.so"]
+    );
+}
+
+#[test]
+fn test_retain_blocks() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_coverage(0, 0x1000, 4)
+        .add_coverage(0, 0x2000, 8)
+        .add_coverage(0, 0x3000, 2)
+        .add_coverage(0, 0x4000, 32)
+        .build()
+        .unwrap();
+
+    coverage.retain_blocks(|bb| bb.size >= 8);
+
+    assert_eq!(coverage.basic_blocks.len(), 2);
+    assert!(coverage.basic_blocks.iter().all(|bb| bb.size >= 8));
+    assert!(coverage.validate().is_ok());
+}
+
+#[test]
+fn test_reindex_modules() {
+    let mut coverage = CoverageData::default();
+    for (id, path) in [(5, "/bin/a"), (2, "/bin/b"), (9, "/bin/c")] {
+        coverage.modules.push(ModuleEntry {
+            id,
+            path: path.to_string(),
+            ..Default::default()
+        });
+    }
+    for (module_id, start) in [(2, 0x10), (9, 0x20), (5, 0x30), (7, 0x40)] {
+        coverage.basic_blocks.push(BasicBlock {
+            module_id,
+            start,
+            size: 4,
+        });
+    }
+
+    match coverage.validate() {
+        Err(Error::ValidationError(msg)) => assert_eq!(
+            msg,
+            "module ids must be sequential from 0; found id 5 at index 0 (expected 0)"
+        ),
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    coverage.reindex_modules();
+    assert!(coverage.validate().is_ok());
+
+    let ids: Vec<_> = coverage.modules.iter().map(|m| m.id).collect();
+    assert_eq!(ids, vec![0, 1, 2]);
+
+    // Blocks follow their modules; the block for unknown id 7 is dropped
+    let blocks: Vec<_> = coverage
+        .basic_blocks
+        .iter()
+        .map(|bb| {
+            (
+                coverage.modules[bb.module_id as usize].path.as_str(),
+                bb.start,
+            )
+        })
+        .collect();
+    assert_eq!(
+        blocks,
+        vec![("/bin/b", 0x10), ("/bin/c", 0x20), ("/bin/a", 0x30)]
+    );
+}
+
+#[test]
+fn test_from_hit_counts() {
+    let modules = vec![ModuleEntry {
+        id: 0,
+        base: 0x400000,
+        end: 0x500000,
+        path: "/bin/program".to_string(),
+        ..Default::default()
+    }];
+    let hot = BasicBlock {
+        module_id: 0,
+        start: 0x1000,
+        size: 32,
+    };
+    let cold = BasicBlock {
+        module_id: 0,
+        start: 0x2000,
+        size: 16,
+    };
+
+    let coverage = CoverageData::from_hit_counts(modules.clone(), &[(hot, 3), (cold, 1)]).unwrap();
+    assert_eq!(coverage.basic_blocks, vec![hot, hot, hot, cold]);
+
+    assert!(matches!(
+        CoverageData::from_hit_counts(modules.clone(), &[(hot, 0)]),
+        Err(Error::ValidationError(_))
+    ));
+
+    let bad_module = BasicBlock {
+        module_id: 1,
+        ..hot
+    };
+    assert!(matches!(
+        CoverageData::from_hit_counts(modules, &[(bad_module, 2)]),
+        Err(Error::ValidationError(_))
+    ));
+}
+
+#[test]
+fn test_coalesce_blocks_by_start() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/bin/b", 0x500000, 0x600000)
+        .add_coverage(0, 0x1000, 16)
+        .add_coverage(0, 0x2000, 8)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x1000, 4)
+        .add_coverage(0, 0x1000, 24)
+        .build()
+        .unwrap();
+
+    coverage.coalesce_blocks_by_start();
+
+    let blocks: Vec<_> = coverage
+        .basic_blocks
+        .iter()
+        .map(|bb| (bb.module_id, bb.start, bb.size))
+        .collect();
+    assert_eq!(
+        blocks,
+        vec![(0, 0x1000, 32), (0, 0x2000, 8), (1, 0x1000, 4)]
+    );
+}
+
+#[test]
+fn test_clear_coverage_and_reuse_modules() {
+    let mut coverage = CoverageData::builder()
+        .flavor("phase1")
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .build()
+        .unwrap();
+
+    coverage.clear_coverage();
+    assert!(coverage.basic_blocks.is_empty());
+    assert_eq!(coverage.modules.len(), 2);
+    assert_eq!(coverage.header.flavor, "phase1");
+    coverage.validate().unwrap();
+
+    let next = CoverageBuilder::from_modules(coverage.modules.clone())
+        .add_coverage(1, 0x3000, 8)
+        .build()
+        .unwrap();
+    assert_eq!(next.modules, coverage.modules);
+    assert_eq!(next.basic_blocks.len(), 1);
+}
+
+#[test]
+fn test_add_absolute_hit() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000000000, 0x7f0200000000)
+        .build()
+        .unwrap();
+
+    coverage.add_absolute_hit(0x7f0000001234, 16).unwrap();
+    assert_eq!(
+        coverage.basic_blocks,
+        vec![BasicBlock {
+            start: 0x1234,
+            size: 16,
+            module_id: 1,
+        }]
+    );
+
+    assert!(matches!(
+        coverage.add_absolute_hit(0x300000, 4),
+        Err(Error::ValidationError(_))
+    ));
+    // Inside the module, but past what a u32 offset can express
+    assert!(matches!(
+        coverage.add_absolute_hit(0x7f0100000000, 4),
+        Err(Error::ValidationError(_))
+    ));
+    assert_eq!(coverage.basic_blocks.len(), 1);
+}
+
+#[test]
+fn test_split_by_module() {
+    let coverage = CoverageData::builder()
+        .flavor("split")
+        .module_version(ModuleTableVersion::V3)
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .add_module("/lib/unused.so", 0x900000, 0xa00000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .add_coverage(0, 0x3000, 8)
+        .build()
+        .unwrap();
+
+    let parts = coverage.split_by_module();
+    assert_eq!(parts.len(), 3);
+    assert_eq!(
+        parts.iter().map(|p| p.basic_blocks.len()).sum::<usize>(),
+        coverage.basic_blocks.len()
+    );
+
+    for (part, module) in parts.iter().zip(&coverage.modules) {
+        part.validate().unwrap();
+        assert_eq!(part.header.flavor, "split");
+        assert_eq!(part.module_version, ModuleTableVersion::V3);
+        assert_eq!(part.modules.len(), 1);
+        assert_eq!(part.modules[0].id, 0);
+        assert_eq!(part.modules[0].path, module.path);
+        assert!(part.basic_blocks.iter().all(|bb| bb.module_id == 0));
+    }
+
+    assert_eq!(
+        parts[1].basic_blocks,
+        vec![BasicBlock {
+            start: 0x2000,
+            size: 16,
+            module_id: 0,
+        }]
+    );
+    assert!(parts[2].basic_blocks.is_empty());
+}
+
+#[test]
+fn test_push_block() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .build()
+        .unwrap();
+
+    let valid = BasicBlock {
+        start: 0x1000,
+        size: 32,
+        module_id: 0,
+    };
+    coverage.push_block(valid).unwrap();
+
+    let invalid = BasicBlock {
+        start: 0x2000,
+        size: 16,
+        module_id: 1,
+    };
+    assert!(matches!(
+        coverage.push_block(invalid),
+        Err(Error::ValidationError(_))
+    ));
+
+    assert_eq!(coverage.basic_blocks, vec![valid]);
+    coverage.validate().unwrap();
+}
+
+#[test]
+fn test_retain_modules() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/usr/lib/b.so", 0x500000, 0x600000)
+        .add_module("/bin/c", 0x600000, 0x700000)
+        .add_module("/usr/lib/d.so", 0x700000, 0x800000)
+        .add_coverage(0, 0x10, 4)
+        .add_coverage(1, 0x20, 4)
+        .add_coverage(2, 0x30, 4)
+        .add_coverage(3, 0x40, 4)
+        .add_coverage(3, 0x50, 4)
+        .build()
+        .unwrap();
+
+    coverage.retain_modules(|m| m.path.starts_with("/usr/lib/"));
+
+    let paths: Vec<_> = coverage.modules.iter().map(|m| m.path.as_str()).collect();
+    assert_eq!(paths, ["/usr/lib/b.so", "/usr/lib/d.so"]);
+    assert_eq!(coverage.modules[0].id, 0);
+    assert_eq!(coverage.modules[1].id, 1);
+    let blocks: Vec<_> = coverage
+        .basic_blocks
+        .iter()
+        .map(|bb| (bb.module_id, bb.start))
+        .collect();
+    assert_eq!(blocks, [(0, 0x20), (1, 0x40), (1, 0x50)]);
+    coverage.validate().unwrap();
+}
+
+#[test]
+fn test_sort_modules_by_base() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/lib/high.so", 0x7f0000, 0x800000)
+        .add_module("/bin/low", 0x400000, 0x500000)
+        .add_module("/lib/mid.so", 0x600000, 0x700000)
+        .add_coverage(0, 0x10, 4)
+        .add_coverage(1, 0x20, 4)
+        .add_coverage(2, 0x30, 4)
+        .build()
+        .unwrap();
+
+    coverage.sort_modules_by_base();
+    coverage.validate().unwrap();
+
+    let modules: Vec<_> = coverage
+        .modules
+        .iter()
+        .map(|m| (m.id, m.path.as_str()))
+        .collect();
+    assert_eq!(
+        modules,
+        [(0, "/bin/low"), (1, "/lib/mid.so"), (2, "/lib/high.so")]
+    );
+    let blocks: Vec<_> = coverage
+        .basic_blocks
+        .iter()
+        .map(|bb| (bb.module_id, bb.start))
+        .collect();
+    assert_eq!(blocks, [(2, 0x10), (0, 0x20), (1, 0x30)]);
+}
+
+#[test]
+fn test_editing_operations_keep_data_valid() {
+    let fresh = || {
+        CoverageData::builder()
+            .add_module("/lib/c.so", 0x900000, 0xa00000)
+            .add_module("/bin/a", 0x400000, 0x500000)
+            .add_module("/lib/b.so", 0x600000, 0x700000)
+            .add_coverage(0, 0x10, 4)
+            .add_coverage(1, 0x20, 4)
+            .add_coverage(2, 0x30, 4)
+            .add_coverage(2, 0x30, 8)
+            .build()
+            .unwrap()
+    };
+
+    let mut coverage = fresh();
+    coverage.retain_modules(|m| m.path != "/bin/a");
+    coverage.assert_valid();
+
+    let mut coverage = fresh();
+    coverage.modules.remove(1);
+    coverage.reindex_modules();
+    coverage.assert_valid();
+    assert_eq!(coverage.basic_blocks.len(), 3);
+
+    let mut coverage = fresh();
+    coverage.sort_modules_by_base();
+    coverage.assert_valid();
+
+    let mut coverage = fresh();
+    coverage.retain_blocks(|bb| bb.size > 4);
+    coverage.assert_valid();
+
+    let mut coverage = fresh();
+    coverage.coalesce_blocks_by_start();
+    coverage.assert_valid();
+
+    let mut coverage = fresh();
+    coverage.clear_coverage();
+    coverage.assert_valid();
+
+    for part in fresh().split_by_module() {
+        part.assert_valid();
+    }
+}
+
+#[test]
+#[should_panic(expected = "Basic block references invalid module ID: 3")]
+fn test_assert_valid_panics_on_stale_block() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .build()
+        .unwrap();
+    coverage.basic_blocks.push(BasicBlock {
+        start: 0x10,
+        size: 4,
+        module_id: 3,
+    });
+    coverage.assert_valid();
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "basic block references a module ID outside the module table")]
+fn test_remap_module_ids_checks_block_refs() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x600000, 0x700000)
+        .add_coverage(1, 0x20, 4)
+        .build()
+        .unwrap();
+    coverage.basic_blocks.push(BasicBlock {
+        start: 0x10,
+        size: 4,
+        module_id: 2,
+    });
+
+    // Only module IDs are checked up front, so the stale block is moved to
+    // another missing module
+    let mapping = HashMap::from([(0, 1), (1, 0), (2, 5)]);
+    let _ = coverage.remap_module_ids(&mapping);
+}
+
+#[test]
+fn test_remap_module_ids() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x600000, 0x700000)
+        .add_module("/lib/c.so", 0x800000, 0x900000)
+        .add_coverage(0, 0x10, 4)
+        .add_coverage(1, 0x20, 4)
+        .add_coverage(2, 0x30, 4)
+        .build()
+        .unwrap();
+
+    let swap = HashMap::from([(0, 1), (1, 0)]);
+    coverage.remap_module_ids(&swap).unwrap();
+    coverage.assert_valid();
+
+    let modules: Vec<_> = coverage
+        .modules
+        .iter()
+        .map(|m| (m.id, m.path.as_str()))
+        .collect();
+    assert_eq!(modules, [(0, "/lib/b.so"), (1, "/bin/a"), (2, "/lib/c.so")]);
+    let blocks: Vec<_> = coverage
+        .basic_blocks
+        .iter()
+        .map(|bb| (bb.module_id, bb.start))
+        .collect();
+    assert_eq!(blocks, [(1, 0x10), (0, 0x20), (2, 0x30)]);
+
+    // Mapping two modules onto the same ID fails and changes nothing
+    let before = coverage.clone();
+    let collide = HashMap::from([(0, 2)]);
+    assert!(matches!(
+        coverage.remap_module_ids(&collide),
+        Err(Error::ValidationError(_))
+    ));
+    assert_eq!(coverage, before);
+
+    // So does leaving a gap
+    let gap = HashMap::from([(2, 5)]);
+    assert!(coverage.remap_module_ids(&gap).is_err());
+    assert_eq!(coverage, before);
+}
+
+#[test]
+fn test_merge_adjacent_blocks() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/bin/b", 0x500000, 0x600000)
+        .add_coverage(0, 0x1010, 16)
+        .add_coverage(1, 0x1000, 16)
+        .add_coverage(0, 0x1000, 16)
+        .add_coverage(0, 0x1020, 16)
+        .add_coverage(0, 0x2000, 8)
+        .add_coverage(0, 0x2004, 2)
+        .build()
+        .unwrap();
+
+    coverage.merge_adjacent_blocks();
+
+    let blocks: Vec<_> = coverage
+        .basic_blocks
+        .iter()
+        .map(|bb| (bb.module_id, bb.start, bb.size))
+        .collect();
+    assert_eq!(
+        blocks,
+        vec![(0, 0x1000, 48), (0, 0x2000, 8), (1, 0x1000, 16)]
+    );
+}
+
+#[test]
+fn test_merge_adjacent_blocks_size_limit() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_coverage(0, 0, u16::MAX)
+        .add_coverage(0, u16::MAX as u32, 16)
+        .build()
+        .unwrap();
+
+    coverage.merge_adjacent_blocks();
+    assert_eq!(coverage.basic_blocks.len(), 2);
+}
+
+#[test]
+fn test_subset_modules() {
+    let coverage = CoverageData::builder()
+        .flavor("subset")
+        .module_version(ModuleTableVersion::V3)
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .add_module("/lib/c.so", 0x900000, 0xa00000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .add_coverage(2, 0x3000, 8)
+        .add_coverage(0, 0x4000, 4)
+        .build()
+        .unwrap();
+
+    let subset = coverage.subset(&[2, 0]).unwrap();
+    subset.validate().unwrap();
+    assert_eq!(subset.header.flavor, "subset");
+    assert_eq!(subset.module_version, ModuleTableVersion::V3);
+    let modules: Vec<_> = subset
+        .modules
+        .iter()
+        .map(|m| (m.id, m.path.as_str()))
+        .collect();
+    assert_eq!(modules, vec![(0, "/lib/c.so"), (1, "/bin/a")]);
+    let blocks: Vec<_> = subset
+        .basic_blocks
+        .iter()
+        .map(|bb| (bb.module_id, bb.start))
+        .collect();
+    assert_eq!(blocks, vec![(1, 0x1000), (0, 0x3000), (1, 0x4000)]);
+
+    assert!(matches!(
+        coverage.subset(&[0, 3]),
+        Err(Error::ValidationError(_))
+    ));
+    assert!(matches!(
+        coverage.subset(&[1, 1]),
+        Err(Error::ValidationError(_))
+    ));
+    assert!(coverage.subset(&[]).unwrap().modules.is_empty());
+}
+
+#[test]
+fn test_overlap_matrix() {
+    let run = |starts: &[u32]| {
+        let mut builder = CoverageData::builder().add_module("/bin/a", 0x400000, 0x500000);
+        for &start in starts {
+            builder = builder.add_coverage(0, start, 4);
+        }
+        builder.build().unwrap()
+    };
+    let a = run(&[0x10, 0x20, 0x30, 0x40]);
+    let b = run(&[0x30, 0x40, 0x50, 0x60]);
+    let c = run(&[0x70]);
+
+    assert_eq!(a.similarity(&b), 2.0 / 6.0);
+    assert_eq!(a.similarity(&a), 1.0);
+    assert_eq!(run(&[]).similarity(&run(&[])), 1.0);
+
+    let matrix = overlap_matrix(&[&a, &b, &c]);
+    assert_eq!(
+        matrix,
+        vec![
+            vec![1.0, 2.0 / 6.0, 0.0],
+            vec![2.0 / 6.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0],
+        ]
+    );
+    assert!(overlap_matrix(&[]).is_empty());
+}
+
+#[test]
+fn test_is_sorted_by_base() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/lib/high.so", 0x7f0000, 0x800000)
+        .add_module("/bin/low", 0x400000, 0x500000)
+        .build()
+        .unwrap();
+    assert!(!coverage.is_sorted_by_base());
+
+    coverage.sort_modules_by_base();
+    assert!(coverage.is_sorted_by_base());
+
+    // Equal bases count as sorted, as do tables with fewer than two modules
+    let equal = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/bin/b", 0x400000, 0x480000)
+        .build()
+        .unwrap();
+    assert!(equal.is_sorted_by_base());
+    assert!(CoverageData::builder().build().unwrap().is_sorted_by_base());
+}
+
+#[test]
+fn test_from_absolute_hits() {
+    let modules = vec![
+        ModuleEntry {
+            id: 0,
+            base: 0x400000,
+            end: 0x500000,
+            path: "/bin/a".to_string(),
+            ..Default::default()
+        },
+        ModuleEntry {
+            id: 1,
+            base: 0x7f0000,
+            end: 0x800000,
+            path: "/lib/b.so".to_string(),
+            ..Default::default()
+        },
+    ];
+    let hits = [(0x401000, 16), (0x900000, 4), (0x7f0200, 8)];
+
+    let coverage = CoverageData::from_absolute_hits(
+        modules.clone(),
+        &hits,
+        "hits",
+        ModuleTableVersion::V3,
+        true,
+    )
+    .unwrap();
+    coverage.validate().unwrap();
+    assert_eq!(coverage.header.flavor, "hits");
+    assert_eq!(coverage.module_version, ModuleTableVersion::V3);
+    let blocks: Vec<_> = coverage
+        .basic_blocks
+        .iter()
+        .map(|bb| (bb.module_id, bb.start, bb.size))
+        .collect();
+    assert_eq!(blocks, vec![(0, 0x1000, 16), (1, 0x200, 8)]);
+
+    match CoverageData::from_absolute_hits(modules, &hits, "hits", ModuleTableVersion::V3, false) {
+        Err(Error::ValidationError(msg)) => {
+            assert_eq!(msg, "No module contains address 0x900000")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn test_module_table_only() {
+    let mut coverage = CoverageData::builder()
+        .flavor("layout")
+        .module_version(ModuleTableVersion::V4)
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .build()
+        .unwrap();
+
+    coverage.bb_table_suffix = Some("basic blocks".to_string());
+    let layout = coverage.module_table_only();
+    layout.validate().unwrap();
+    assert!(layout.basic_blocks.is_empty());
+    assert_eq!(layout.modules, coverage.modules);
+    assert_eq!(layout.module_version, ModuleTableVersion::V4);
+    assert_eq!(layout.header, coverage.header);
+    assert_eq!(coverage.block_count(), 2);
+
+    let mut buffer = Vec::new();
+    drcov::to_writer(&layout, &mut buffer).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+    assert_eq!(text.lines().last(), Some("BB Table: 0 bbs"));
+    assert!(text.ends_with("\nBB Table: 0 bbs\n"));
+}
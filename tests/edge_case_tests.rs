@@ -1,4 +1,4 @@
-use drcov::{from_reader, Error};
+use drcov::{from_reader, from_reader_with_options, Error, ParseOptions};
 use std::io::Cursor;
 
 #[test]
@@ -257,3 +257,252 @@ fn test_special_characters_in_paths() {
     let coverage = from_reader(Cursor::new(comma_path)).unwrap();
     assert_eq!(coverage.modules[0].path, "/path,with,commas/test");
 }
+
+#[test]
+fn test_decimal_and_hex_address_fields() {
+    let hex = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 0 bbs\n";
+    let decimal = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 4194304, 5242880, 4198400, /bin/test\nBB Table: 0 bbs\n";
+
+    let from_hex = from_reader(Cursor::new(hex)).unwrap();
+    let from_decimal = from_reader(Cursor::new(decimal)).unwrap();
+    assert_eq!(from_decimal.modules[0].base, 0x400000);
+    assert_eq!(from_decimal.modules[0].end, 0x500000);
+    assert_eq!(from_decimal.modules[0].entry, Some(0x401000));
+    assert_eq!(from_hex.modules, from_decimal.modules);
+
+    // Values that are neither hex nor decimal are rejected
+    let garbage = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0xzz, 0x401000, /bin/test\nBB Table: 0 bbs\n";
+    assert!(matches!(
+        from_reader(Cursor::new(garbage)),
+        Err(Error::InvalidModuleTable(_))
+    ));
+}
+
+#[test]
+fn test_reject_trailing_data() {
+    let header = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 1 bbs\n";
+    let mut data = Vec::new();
+    data.extend_from_slice(header.as_bytes());
+    data.extend_from_slice(&0x1000u32.to_le_bytes());
+    data.extend_from_slice(&32u16.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+
+    let strict = ParseOptions {
+        reject_trailing_data: true,
+        ..Default::default()
+    };
+
+    // A clean file parses in strict mode
+    assert!(from_reader_with_options(Cursor::new(&data), &strict).is_ok());
+
+    data.extend_from_slice(b"junk");
+
+    // Lenient by default
+    let coverage = from_reader(Cursor::new(&data)).unwrap();
+    assert_eq!(coverage.basic_blocks.len(), 1);
+
+    assert!(matches!(
+        from_reader_with_options(Cursor::new(&data), &strict),
+        Err(Error::InvalidFormat(_))
+    ));
+}
+
+#[test]
+fn test_module_count_exceeds_rows() {
+    let versioned = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 2, count 5\nColumns: id, base, end, entry, path\n0, 0x400000, 0x500000, 0x401000, /bin/a\n1, 0x500000, 0x600000, 0x501000, /bin/b\n2, 0x600000, 0x700000, 0x601000, /bin/c\n";
+    match from_reader(Cursor::new(versioned)) {
+        Err(Error::InvalidModuleTable(msg)) => {
+            assert_eq!(msg, "Expected 5 modules, found 3 before EOF")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    let legacy = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 3\n0, 0x400000, 0x500000, 0x401000, /bin/a\nBB Table: 0 bbs\n";
+    match from_reader(Cursor::new(legacy)) {
+        Err(Error::InvalidModuleTable(msg)) => {
+            assert_eq!(msg, "Expected 3 modules, found 1 before BB table")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
+
+#[test]
+fn test_utf8_bom() {
+    let content = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 0\nBB Table: 0 bbs\n";
+    let mut data = vec![0xEF, 0xBB, 0xBF];
+    data.extend_from_slice(content);
+
+    let coverage = from_reader(Cursor::new(&data)).unwrap();
+    assert_eq!(coverage.header.version, 2);
+    assert_eq!(coverage.header.flavor, "test");
+    assert_eq!(drcov::from_bytes(&data).unwrap(), coverage);
+
+    // Only a single leading BOM is stripped
+    let mut doubled = vec![0xEF, 0xBB, 0xBF];
+    doubled.extend_from_slice(&data);
+    assert!(matches!(
+        from_reader(Cursor::new(doubled)),
+        Err(Error::InvalidFormat(_))
+    ));
+}
+
+#[test]
+fn test_comment_lines() {
+    let content = "# generated by foo\n# version 1.2\n\nDRCOV VERSION: 2\nDRCOV FLAVOR: test\n# modules follow\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\n\nBB Table: 0 bbs\n";
+
+    // Strict by default
+    assert!(matches!(
+        from_reader(Cursor::new(content)),
+        Err(Error::InvalidFormat(_))
+    ));
+
+    let options = ParseOptions {
+        allow_comments: true,
+        ..Default::default()
+    };
+    let coverage = from_reader_with_options(Cursor::new(content), &options).unwrap();
+    assert_eq!(coverage.header.flavor, "test");
+    assert_eq!(coverage.modules.len(), 1);
+    assert_eq!(coverage.modules[0].path, "/bin/test");
+}
+
+#[test]
+fn test_parse_count_limits() {
+    let options = ParseOptions {
+        max_modules: Some(1000),
+        max_blocks: Some(1000),
+        ..Default::default()
+    };
+
+    // Only the header counts are present: the limits trip before any entry
+    // data would be read
+    let huge_modules =
+        "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 2, count 99999\n";
+    match from_reader_with_options(Cursor::new(huge_modules), &options) {
+        Err(Error::InvalidFormat(msg)) => {
+            assert_eq!(msg, "module count 99999 exceeds limit 1000")
+        }
+        other => panic!("expected module limit error, got {other:?}"),
+    }
+
+    let huge_blocks = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 5000000 bbs\n";
+    match from_reader_with_options(Cursor::new(huge_blocks), &options) {
+        Err(Error::InvalidFormat(msg)) => {
+            assert_eq!(msg, "block count 5000000 exceeds limit 1000")
+        }
+        other => panic!("expected block limit error, got {other:?}"),
+    }
+
+    // Counts at the limit are accepted
+    let within = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 0 bbs\n";
+    let limits = ParseOptions {
+        max_modules: Some(1),
+        max_blocks: Some(0),
+        ..Default::default()
+    };
+    assert!(from_reader_with_options(Cursor::new(within), &limits).is_ok());
+}
+
+#[test]
+fn test_negative_offset_rejected() {
+    // Offsets are unsigned; a negative value must not silently parse as 0
+    let data = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 4, count 1\nColumns: id, containing_id, start, end, entry, offset, path\n0, -1, 0x400000, 0x500000, 0x401000, -0x1000, /bin/test\nBB Table: 0 bbs\n";
+    match from_reader(Cursor::new(data)) {
+        Err(Error::InvalidModuleTable(msg)) => {
+            assert_eq!(msg, "Negative 'offset' value not supported: -0x1000")
+        }
+        other => panic!("expected negative offset error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_flavor_optional() {
+    let data = "DRCOV VERSION: 2\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 0 bbs\n";
+
+    // Strict by default
+    assert!(matches!(
+        from_reader(Cursor::new(data)),
+        Err(Error::InvalidFormat(_))
+    ));
+
+    let options = ParseOptions {
+        flavor_optional: true,
+        ..Default::default()
+    };
+    let coverage = from_reader_with_options(Cursor::new(data), &options).unwrap();
+    assert_eq!(coverage.header.flavor, "");
+    assert_eq!(coverage.modules.len(), 1);
+    assert_eq!(coverage.modules[0].path, "/bin/test");
+
+    // A present flavor line is still honored
+    let with_flavor = "DRCOV VERSION: 2\nDRCOV FLAVOR: tool\nModule Table: 0\nBB Table: 0 bbs\n";
+    let coverage = from_reader_with_options(Cursor::new(with_flavor), &options).unwrap();
+    assert_eq!(coverage.header.flavor, "tool");
+}
+
+#[test]
+fn test_truncated_bb_table_reports_progress() {
+    let header = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 5 bbs\n";
+    let mut data = header.as_bytes().to_vec();
+    data.extend_from_slice(&[0u8; 3 * 8 + 5]); // 3 full records and part of a 4th
+
+    match from_reader(Cursor::new(data)) {
+        Err(Error::Io(e)) => {
+            assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+            assert_eq!(e.to_string(), "Read 3 of 5 basic blocks before EOF");
+        }
+        other => panic!("expected truncation error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_columns_line_without_final_newline() {
+    // A table ending at the Columns line, with no newline, is complete when
+    // it declares no modules
+    let empty =
+        "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 2, count 0\nColumns: id, base, end, entry, path";
+    let coverage = from_reader(Cursor::new(empty)).unwrap();
+    assert!(coverage.modules.is_empty());
+    assert_eq!(
+        coverage.source_columns.as_deref(),
+        Some(&["id", "base", "end", "entry", "path"].map(String::from)[..])
+    );
+
+    // Declared modules missing after it are reported as such
+    let truncated = empty.replace("count 0", "count 1");
+    match from_reader(Cursor::new(&truncated)) {
+        Err(Error::InvalidModuleTable(msg)) => {
+            assert_eq!(msg, "Expected 1 modules, found 0 before EOF")
+        }
+        other => panic!("unexpected result: {other:?}"),
+    }
+
+    // Rows glued onto the Columns line cannot be told apart from column
+    // names, so they are rejected rather than misread
+    let glued = format!("{truncated}0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 0 bbs\n");
+    assert!(from_reader(Cursor::new(glued)).is_err());
+}
+
+#[test]
+fn test_module_rows_without_final_newline() {
+    // The file ends right after the last module row
+    let rows = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 2, count 2\nColumns: id, base, end, entry, path\n0, 0x400000, 0x500000, 0x401000, /bin/test\n1, 0x600000, 0x700000, 0x0, /lib/libc.so";
+    let coverage = from_reader(Cursor::new(rows)).unwrap();
+    let modules: Vec<_> = coverage
+        .modules
+        .iter()
+        .map(|m| (m.id, m.base, m.end, m.entry, m.path.as_str()))
+        .collect();
+    assert_eq!(
+        modules,
+        [
+            (0, 0x400000, 0x500000, Some(0x401000), "/bin/test"),
+            (1, 0x600000, 0x700000, Some(0), "/lib/libc.so"),
+        ]
+    );
+    assert!(coverage.basic_blocks.is_empty());
+
+    // Likewise when an empty BB table line lacking its newline follows
+    let with_bb_table = format!("{rows}\nBB Table: 0 bbs");
+    assert_eq!(from_reader(Cursor::new(with_bb_table)).unwrap(), coverage);
+}
@@ -1,4 +1,8 @@
-use drcov::{from_reader, to_writer, CoverageData, ModuleEntry, ModuleTableVersion};
+use drcov::{
+    from_reader, from_reader_multi, from_reader_wide, from_reader_with_options, to_writer,
+    to_writer_wide, to_writer_with_options, BasicBlockWide, CoverageData, Error, ModuleEntry,
+    ModuleTableVersion, ParseOptions, WriteOptions,
+};
 use std::io::Cursor;
 
 #[test]
@@ -16,7 +20,7 @@ fn test_legacy_format_parsing() {
     assert_eq!(coverage.modules[0].id, 0);
     assert_eq!(coverage.modules[0].base, 0x400000);
     assert_eq!(coverage.modules[0].end, 0x500000);
-    assert_eq!(coverage.modules[0].entry, 0x401000);
+    assert_eq!(coverage.modules[0].entry, Some(0x401000));
 
     // Optional fields should be None/default for legacy
     assert_eq!(coverage.modules[0].containing_id, None);
@@ -108,7 +112,7 @@ fn test_format_version_round_trip() {
                 id: 0,
                 base: 0x400000,
                 end: 0x500000,
-                entry: 0x401000,
+                entry: Some(0x401000),
                 path: "/bin/test".to_string(),
                 containing_id: if version >= ModuleTableVersion::V3 {
                     Some(-1)
@@ -235,7 +239,7 @@ fn test_column_order_variations() {
     assert_eq!(coverage.modules[0].id, 0);
     assert_eq!(coverage.modules[0].base, 0x400000);
     assert_eq!(coverage.modules[0].end, 0x500000);
-    assert_eq!(coverage.modules[0].entry, 0x401000);
+    assert_eq!(coverage.modules[0].entry, Some(0x401000));
     assert_eq!(coverage.modules[0].containing_id, Some(-1));
     assert_eq!(coverage.modules[0].offset, Some(0x1000));
     assert_eq!(coverage.modules[0].checksum, Some(0x12345678));
@@ -268,7 +272,7 @@ fn test_writer_format_selection() {
             id: 0,
             base: 0x400000,
             end: 0x500000,
-            entry: 0x401000,
+            entry: Some(0x401000),
             path: "/bin/v4".to_string(),
             containing_id: Some(-1),
             offset: Some(0x1000),
@@ -289,3 +293,446 @@ fn test_writer_format_selection() {
     assert!(output.contains("0x12345678"));
     assert!(output.contains("0x87654321"));
 }
+
+#[test]
+fn test_bb_table_suffix_roundtrip() {
+    let base = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x0000000000400000, 0x0000000000450000, 0x0000000000401000, /bin/test\n";
+
+    for (bb_line, suffix) in [
+        ("BB Table: 0\n", Some("")),
+        ("BB Table: 0 blocks\n", Some("blocks")),
+        ("BB Table: 0 bbs\n", None),
+    ] {
+        let original = format!("{base}{bb_line}");
+        let coverage = from_reader(Cursor::new(&original)).unwrap();
+        assert_eq!(coverage.bb_table_suffix.as_deref(), suffix);
+
+        let mut buffer = Vec::new();
+        to_writer(&coverage, &mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), original);
+    }
+}
+
+#[test]
+fn test_concatenated_sections() {
+    let first = CoverageData::builder()
+        .flavor("thread_1")
+        .module_version(ModuleTableVersion::V2)
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(0, 0x2000, 16)
+        .build()
+        .unwrap();
+    let second = CoverageData::builder()
+        .flavor("thread_2")
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(1, 0x3000, 8)
+        .build()
+        .unwrap();
+
+    let mut buffer = Vec::new();
+    to_writer(&first, &mut buffer).unwrap();
+    to_writer(&second, &mut buffer).unwrap();
+
+    let sections = from_reader_multi(Cursor::new(buffer)).unwrap();
+    assert_eq!(sections, vec![first.clone(), second]);
+
+    // A single section yields a one-element vec
+    let mut buffer = Vec::new();
+    to_writer(&first, &mut buffer).unwrap();
+    assert_eq!(from_reader_multi(Cursor::new(buffer)).unwrap(), vec![first]);
+}
+
+#[test]
+fn test_source_columns_recorded() {
+    let v4 = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 4, count 1\nColumns: id, containing_id, start, end, entry, offset, checksum, timestamp, path\n0, -1, 0x400000, 0x500000, 0x401000, 0x0, 0x12345678, 0x87654321, /bin/test\nBB Table: 0 bbs\n";
+    let coverage = from_reader(Cursor::new(v4)).unwrap();
+    assert_eq!(
+        coverage.source_columns.unwrap(),
+        vec![
+            "id",
+            "containing_id",
+            "start",
+            "end",
+            "entry",
+            "offset",
+            "checksum",
+            "timestamp",
+            "path"
+        ]
+    );
+
+    let legacy = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 0 bbs\n";
+    assert_eq!(
+        from_reader(Cursor::new(legacy)).unwrap().source_columns,
+        None
+    );
+
+    // Synthetic data has no source layout
+    assert_eq!(
+        CoverageData::builder().build().unwrap().source_columns,
+        None
+    );
+}
+
+#[test]
+fn test_case_insensitive_headers() {
+    let lowercase = "drcov version: 2\ndrcov flavor: test\nmodule table: 1\n0, 0x400000, 0x500000, 0x401000, /bin/test\nbb table: 1 bbs\n";
+    let mut data = lowercase.as_bytes().to_vec();
+    data.extend_from_slice(&0x1000u32.to_le_bytes());
+    data.extend_from_slice(&32u16.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+
+    // Exact matching by default
+    assert!(from_reader(Cursor::new(&data)).is_err());
+
+    let options = ParseOptions {
+        case_insensitive_headers: true,
+        ..Default::default()
+    };
+    let coverage = from_reader_with_options(Cursor::new(&data), &options).unwrap();
+    assert_eq!(coverage.header.version, 2);
+    assert_eq!(coverage.header.flavor, "test");
+    assert_eq!(coverage.modules[0].path, "/bin/test");
+    assert_eq!(coverage.basic_blocks.len(), 1);
+}
+
+#[test]
+fn test_module_table_header_spacing() {
+    for header in [
+        "Module Table: version 4,count 1",
+        "Module Table: version  4 , count  1",
+    ] {
+        let data = format!(
+            "DRCOV VERSION: 2\nDRCOV FLAVOR: test\n{header}\nColumns: id, containing_id, start, end, entry, offset, path\n0, -1, 0x400000, 0x500000, 0x401000, 0x0, /bin/test\nBB Table: 0 bbs\n"
+        );
+        let coverage = from_reader(Cursor::new(data)).unwrap();
+        assert_eq!(coverage.module_version, ModuleTableVersion::V4, "{header}");
+        assert_eq!(coverage.modules.len(), 1, "{header}");
+    }
+}
+
+#[test]
+fn test_trim_path_option() {
+    let mut data = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 1\n0, 0x400000, 0x500000, 0x401000, /tmp/ dir \r\nBB Table: 0 bbs\n".to_vec();
+
+    let coverage = from_reader(Cursor::new(&data)).unwrap();
+    assert_eq!(coverage.modules[0].path, "/tmp/ dir");
+
+    let options = ParseOptions {
+        trim_path: false,
+        ..Default::default()
+    };
+    let coverage = from_reader_with_options(Cursor::new(&data), &options).unwrap();
+    assert_eq!(coverage.modules[0].path, "/tmp/ dir ");
+
+    // Only the path keeps its whitespace; numeric columns are still trimmed
+    data = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 2, count 1\nColumns: id, base, end, entry, path\n0 ,  0x400000,0x500000, 0x401000,  spaced \nBB Table: 0 bbs\n".to_vec();
+    let coverage = from_reader_with_options(Cursor::new(&data), &options).unwrap();
+    assert_eq!(coverage.modules[0].base, 0x400000);
+    assert_eq!(coverage.modules[0].path, " spaced ");
+}
+
+#[test]
+fn test_module_table_count_without_version() {
+    let data = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: count 2\nColumns: id, base, end, entry, path\n0, 0x400000, 0x500000, 0x401000, /bin/test\n1, 0x7f0000, 0x800000, 0x0, /lib/libc.so\nBB Table: 0 bbs\n";
+
+    let coverage = from_reader(Cursor::new(data)).unwrap();
+    assert_eq!(coverage.module_version, ModuleTableVersion::V2);
+    assert_eq!(coverage.modules.len(), 2);
+    assert_eq!(coverage.modules[1].path, "/lib/libc.so");
+    assert_eq!(coverage.modules[1].base, 0x7f0000);
+}
+
+#[test]
+fn test_flavor_metadata_roundtrip() {
+    let data = "DRCOV VERSION: 2\nDRCOV FLAVOR: drcov-64\tbuild=1234 commit=abc\nModule Table: 0\nBB Table: 0 bbs\n";
+
+    let coverage = from_reader(Cursor::new(data)).unwrap();
+    assert_eq!(coverage.header.flavor, "drcov-64");
+    assert_eq!(
+        coverage.header.flavor_metadata.as_deref(),
+        Some("build=1234 commit=abc")
+    );
+
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+    let output = String::from_utf8(buffer).unwrap();
+    assert!(output.contains("DRCOV FLAVOR: drcov-64\tbuild=1234 commit=abc\n"));
+
+    let plain = from_reader(Cursor::new(
+        "DRCOV VERSION: 2\nDRCOV FLAVOR: drcov\nModule Table: 0\nBB Table: 0 bbs\n",
+    ))
+    .unwrap();
+    assert_eq!(plain.header.flavor, "drcov");
+    assert_eq!(plain.header.flavor_metadata, None);
+}
+
+#[test]
+fn test_tab_delimited_columns_header() {
+    let data = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 2, count 1\nColumns:\tid\tbase\tend\tentry\tpath\n0, 0x400000, 0x500000, 0x401000, /bin/test\nBB Table: 0 bbs\n";
+
+    let coverage = from_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        coverage.source_columns,
+        Some(vec![
+            "id".to_string(),
+            "base".to_string(),
+            "end".to_string(),
+            "entry".to_string(),
+            "path".to_string(),
+        ])
+    );
+    assert_eq!(coverage.modules[0].base, 0x400000);
+    assert_eq!(coverage.modules[0].entry, Some(0x401000));
+    assert_eq!(coverage.modules[0].path, "/bin/test");
+}
+
+#[test]
+fn test_wide_bb_layout_roundtrip() {
+    let coverage = CoverageData::builder()
+        .module_version(ModuleTableVersion::V4)
+        .add_module("/bin/huge", 0x100000000, 0x300000000)
+        .add_coverage(0, 0x40, 8)
+        .build()
+        .unwrap();
+    let blocks = vec![
+        BasicBlockWide {
+            start: 0x1_2345_6780,
+            size: 0x20,
+            module_id: 0,
+        },
+        BasicBlockWide {
+            start: 0x10,
+            size: 0x1_0000,
+            module_id: 0,
+        },
+    ];
+
+    // Only the wide blocks are written, not those held in the data
+    let mut buffer = Vec::new();
+    to_writer_wide(&coverage, &blocks, &mut buffer, &WriteOptions::default()).unwrap();
+    assert!(buffer.ends_with(&blocks[1].to_bytes()));
+    assert_eq!(
+        buffer.len()
+            - buffer
+                .windows(13)
+                .position(|w| w == b"BB Table: 2 b")
+                .unwrap(),
+        "BB Table: 2 bbs\n".len() + 2 * 16
+    );
+
+    let (parsed, parsed_blocks) =
+        from_reader_wide(Cursor::new(&buffer), &ParseOptions::default()).unwrap();
+    assert!(parsed.basic_blocks.is_empty());
+    assert_eq!(parsed.modules[0].base, 0x100000000);
+    assert_eq!(parsed_blocks, blocks);
+
+    let dangling = [BasicBlockWide {
+        start: 0,
+        size: 4,
+        module_id: 1,
+    }];
+    assert!(matches!(
+        to_writer_wide(
+            &coverage,
+            &dangling,
+            &mut Vec::new(),
+            &WriteOptions::default()
+        ),
+        Err(Error::ValidationError(_))
+    ));
+}
+
+#[test]
+fn test_missing_entry_column_roundtrip() {
+    let without_entry = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 2, count 1\nColumns: id, base, end, path\n0, 0x400000, 0x500000, /bin/test\nBB Table: 0 bbs\n";
+    let zero_entry = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: version 2, count 1\nColumns: id, base, end, entry, path\n0, 0x400000, 0x500000, 0x0, /bin/test\nBB Table: 0 bbs\n";
+
+    let rewrite = |input: &str| {
+        let coverage = from_reader(Cursor::new(input)).unwrap();
+        let mut buffer = Vec::new();
+        to_writer(&coverage, &mut buffer).unwrap();
+        (coverage, String::from_utf8(buffer).unwrap())
+    };
+
+    let (absent, absent_text) = rewrite(without_entry);
+    let (present, present_text) = rewrite(zero_entry);
+    assert_eq!(absent.modules[0].entry, None);
+    assert_eq!(present.modules[0].entry, Some(0));
+    assert_ne!(absent.modules, present.modules);
+    assert!(absent_text.contains(
+        "Columns: id, base, end, path\n0, 0x0000000000400000, 0x0000000000500000, /bin/test\n"
+    ));
+    assert!(present_text.contains("Columns: id, base, end, entry, path\n"));
+    assert!(present_text.contains(", 0x0000000000000000, /bin/test\n"));
+
+    // Setting an entry point brings the column back
+    let mut updated = absent.clone();
+    updated.modules[0].entry = Some(0x401000);
+    let mut buffer = Vec::new();
+    to_writer(&updated, &mut buffer).unwrap();
+    let reparsed = from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(reparsed.modules[0].entry, Some(0x401000));
+
+    // A module added after parsing decides for itself
+    let mut added = absent;
+    added.modules.push(
+        ModuleEntry::builder("/lib/libc.so", 0x600000, 0x700000)
+            .id(1)
+            .build(),
+    );
+    let (reparsed, text) = rewrite(&{
+        let mut buffer = Vec::new();
+        to_writer(&added, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    });
+    assert!(text.contains("Columns: id, base, end, entry, path\n"));
+    assert_eq!(reparsed.modules[0].entry, Some(0));
+    assert_eq!(reparsed.modules[1].entry, Some(0));
+
+    // Subsetting back to the parsed module leaves the column out again
+    let subset = added.subset(&[0]).unwrap();
+    let mut buffer = Vec::new();
+    to_writer(&subset, &mut buffer).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+    assert!(text.contains("Columns: id, base, end, path\n"));
+    assert_eq!(
+        from_reader(Cursor::new(&text)).unwrap().modules[0].entry,
+        None
+    );
+}
+
+#[test]
+fn test_entry_column_written_for_built_modules() {
+    let coverage = CoverageData::builder()
+        .module_version(ModuleTableVersion::V2)
+        .add_full_module(ModuleEntry {
+            id: 0,
+            base: 0x400000,
+            end: 0x500000,
+            path: "/bin/a".to_string(),
+            ..Default::default()
+        })
+        .add_full_module(ModuleEntry {
+            id: 1,
+            base: 0x600000,
+            end: 0x700000,
+            entry: None,
+            path: "/lib/b.so".to_string(),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    assert_eq!(coverage.modules[0].entry, Some(0));
+
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+    assert!(text.contains("Columns: id, base, end, entry, path\n"));
+    assert!(
+        text.contains("1, 0x0000000000600000, 0x0000000000700000, 0x0000000000000000, /lib/b.so\n")
+    );
+}
+
+#[test]
+fn test_custom_column_order() {
+    let coverage = CoverageData::builder()
+        .flavor("ordered")
+        .module_version(ModuleTableVersion::V4)
+        .add_full_module(ModuleEntry {
+            id: 0,
+            base: 0x400000,
+            end: 0x500000,
+            entry: Some(0x401000),
+            path: "/bin/test".to_string(),
+            containing_id: Some(0),
+            offset: Some(0x1000),
+            checksum: None,
+            timestamp: None,
+        })
+        .add_coverage(0, 0x1000, 16)
+        .build()
+        .unwrap();
+
+    let order = [
+        "path",
+        "id",
+        "start",
+        "end",
+        "entry",
+        "offset",
+        "containing_id",
+    ];
+    let options = WriteOptions {
+        column_order: Some(order.iter().map(|c| c.to_string()).collect()),
+        ..Default::default()
+    };
+    let mut buffer = Vec::new();
+    to_writer_with_options(&coverage, &mut buffer, &options).unwrap();
+    let text = String::from_utf8_lossy(&buffer);
+    assert!(text.contains(
+        "Columns: path, id, start, end, entry, offset, containing_id\n\
+         /bin/test, 0, 0x0000000000400000, 0x0000000000500000, 0x0000000000401000, 0x1000, 0\n"
+    ));
+
+    let parsed = from_reader(Cursor::new(&buffer)).unwrap();
+    assert_eq!(parsed.modules, coverage.modules);
+    assert_eq!(parsed.basic_blocks, coverage.basic_blocks);
+
+    // Missing or unknown columns are rejected before anything is written
+    for bad in [
+        &order[1..],
+        &[
+            "path",
+            "id",
+            "base",
+            "end",
+            "entry",
+            "offset",
+            "containing_id",
+        ][..],
+    ] {
+        let options = WriteOptions {
+            column_order: Some(bad.iter().map(|c| c.to_string()).collect()),
+            ..Default::default()
+        };
+        let mut buffer = Vec::new();
+        assert!(matches!(
+            to_writer_with_options(&coverage, &mut buffer, &options),
+            Err(Error::ValidationError(_))
+        ));
+        assert!(buffer.is_empty());
+    }
+}
+
+#[test]
+fn test_extra_header_lines_roundtrip() {
+    let input = "DRCOV VERSION: 2\nDRCOV FLAVOR: test\nDRCOV COMMENT: built 2024\nX-Tool: fuzz run 7\nModule Table: version 2, count 1\nColumns: id, base, end, entry, path\n0, 0x0000000000400000, 0x0000000000500000, 0x0000000000401000, /bin/test\nBB Table: 0 bbs\n";
+
+    // Without the option the extra lines are still rejected
+    assert!(from_reader(Cursor::new(input)).is_err());
+
+    let options = ParseOptions {
+        preserve_extra_headers: true,
+        ..Default::default()
+    };
+    let coverage = from_reader_with_options(Cursor::new(input), &options).unwrap();
+    assert_eq!(
+        coverage.extra_headers,
+        vec!["DRCOV COMMENT: built 2024", "X-Tool: fuzz run 7"]
+    );
+    assert_eq!(coverage.modules.len(), 1);
+
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+    assert_eq!(String::from_utf8(buffer).unwrap(), input);
+
+    let mut invalid = coverage.clone();
+    invalid.extra_headers.push("Module Table: 5".to_string());
+    assert!(matches!(
+        to_writer(&invalid, &mut Vec::new()),
+        Err(Error::ValidationError(_))
+    ));
+}
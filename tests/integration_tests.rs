@@ -1,4 +1,8 @@
-use drcov::{from_reader, to_writer, CoverageData, ModuleTableVersion};
+use drcov::{
+    count_blocks, from_reader, from_reader_after_header, from_split, parse_header, parse_visit,
+    read_modules_only, to_writer, BasicBlock, CoverageData, DrcovVisitor, FileHeader, ModuleEntry,
+    ModuleTableVersion,
+};
 use std::io::Cursor;
 use tempfile::NamedTempFile;
 
@@ -209,3 +213,180 @@ fn test_builder_validation() {
 
     assert!(invalid_coverage.is_err());
 }
+
+#[test]
+fn test_file_errors_include_path() {
+    let missing = std::env::temp_dir().join("drcov_missing_dir/missing.drcov");
+
+    let err = drcov::from_file(&missing).unwrap_err();
+    assert!(matches!(err, drcov::Error::IoWithPath { .. }));
+    assert_eq!(err.kind(), drcov::ErrorKind::Io);
+    assert!(err.to_string().contains(&missing.display().to_string()));
+    assert!(std::error::Error::source(&err).is_some());
+
+    let coverage = CoverageData::builder().build().unwrap();
+    let err = drcov::to_file(&coverage, &missing).unwrap_err();
+    assert!(err.to_string().contains("missing.drcov"));
+
+    // Parse errors from files are not wrapped
+    let temp_file = NamedTempFile::new().unwrap();
+    std::fs::write(temp_file.path(), "DRCOV VERSION: 3\n").unwrap();
+    assert!(matches!(
+        drcov::from_file(temp_file.path()),
+        Err(drcov::Error::UnsupportedVersion(3))
+    ));
+}
+
+#[test]
+fn test_read_modules_only() {
+    let coverage = CoverageData::builder()
+        .flavor("catalog")
+        .module_version(ModuleTableVersion::V3)
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .build()
+        .unwrap();
+
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+    // Cut the BB body short: a full parse fails, but the block bytes are
+    // never looked at when reading modules only
+    buffer.truncate(buffer.len() - 12);
+    assert!(from_reader(Cursor::new(&buffer)).is_err());
+
+    let (header, version, modules) = read_modules_only(Cursor::new(&buffer)).unwrap();
+    assert_eq!(header.flavor, "catalog");
+    assert_eq!(version, ModuleTableVersion::V3);
+    assert_eq!(modules.len(), 2);
+    assert_eq!(modules[1].path, "/lib/libc.so");
+    assert_eq!(modules[1].base, 0x7fff00000000);
+}
+
+#[test]
+fn test_staged_header_parsing() {
+    let coverage = CoverageData::builder()
+        .flavor("staged")
+        .module_version(ModuleTableVersion::V2)
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_coverage(0, 0x1000, 32)
+        .build()
+        .unwrap();
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+
+    let mut reader = Cursor::new(&buffer);
+    let header = parse_header(&mut reader).unwrap();
+    assert_eq!(header.flavor, "staged");
+    let staged = from_reader_after_header(&mut reader, header).unwrap();
+
+    assert_eq!(staged, from_reader(Cursor::new(&buffer)).unwrap());
+}
+
+#[test]
+fn test_from_split_streams() {
+    let coverage = CoverageData::builder()
+        .flavor("split")
+        .module_version(ModuleTableVersion::V2)
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .add_coverage(0, 0x3000, 8)
+        .build()
+        .unwrap();
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+
+    let body_len = coverage.basic_blocks.len() * 8;
+    let (text, binary) = buffer.split_at(buffer.len() - body_len);
+    let joined = from_reader(Cursor::new(&buffer)).unwrap();
+
+    // With the BB Table line in the text stream
+    assert_eq!(from_split(text, binary, 3).unwrap(), joined);
+
+    // Without it
+    let modules_only = &text[..text.len() - "BB Table: 3 bbs\n".len()];
+    let reassembled = from_split(modules_only, binary, 3).unwrap();
+    assert_eq!(reassembled.basic_blocks, coverage.basic_blocks);
+    assert_eq!(reassembled.modules, joined.modules);
+
+    // A count disagreeing with the BB Table line is rejected
+    assert!(from_split(text, binary, 2).is_err());
+}
+
+#[derive(Default)]
+struct CountingVisitor {
+    flavor: String,
+    modules: usize,
+    blocks: usize,
+}
+
+impl DrcovVisitor for CountingVisitor {
+    fn on_header(&mut self, header: &FileHeader) {
+        self.flavor = header.flavor.clone();
+    }
+
+    fn on_module(&mut self, _module: &ModuleEntry) {
+        self.modules += 1;
+    }
+
+    fn on_block(&mut self, _block: &BasicBlock) {
+        self.blocks += 1;
+    }
+}
+
+#[test]
+fn test_parse_visit_counts() {
+    let coverage = CoverageData::builder()
+        .flavor("visited")
+        .module_version(ModuleTableVersion::V4)
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .add_coverage(0, 0x3000, 8)
+        .build()
+        .unwrap();
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+
+    let mut visitor = CountingVisitor::default();
+    parse_visit(Cursor::new(&buffer), &mut visitor).unwrap();
+    assert_eq!(visitor.flavor, "visited");
+    assert_eq!(visitor.modules, 2);
+    assert_eq!(visitor.blocks, 3);
+
+    // A truncated BB table still fails
+    buffer.truncate(buffer.len() - 4);
+    assert!(parse_visit(Cursor::new(&buffer), &mut CountingVisitor::default()).is_err());
+}
+
+#[test]
+fn test_count_blocks_skips_body() {
+    let coverage = CoverageData::builder()
+        .flavor("counted")
+        .module_version(ModuleTableVersion::V3)
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .add_coverage(0, 0x3000, 8)
+        .build()
+        .unwrap();
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+
+    // Drop the block records entirely: a full parse fails, but the count
+    // comes from the header line alone
+    buffer.truncate(buffer.len() - 24);
+    assert!(from_reader(Cursor::new(&buffer)).is_err());
+    let mut reader = Cursor::new(&buffer);
+    assert_eq!(count_blocks(&mut reader).unwrap(), 3);
+    assert_eq!(reader.position() as usize, buffer.len());
+
+    let no_bb_table = b"DRCOV VERSION: 2\nDRCOV FLAVOR: test\nModule Table: 0\n";
+    assert_eq!(count_blocks(&mut &no_bb_table[..]).unwrap(), 0);
+    assert!(count_blocks(&mut &b"not a drcov file\n"[..]).is_err());
+}
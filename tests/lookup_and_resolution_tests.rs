@@ -1,4 +1,4 @@
-use drcov::{CoverageData, ModuleEntry};
+use drcov::{BasicBlock, CoverageData, ModuleEntry, NoSymbolResolver};
 
 #[test]
 fn test_find_module_by_id() {
@@ -151,7 +151,7 @@ fn test_module_contains_address() {
         id: 0,
         base: 0x400000,
         end: 0x500000,
-        entry: 0x401000,
+        entry: Some(0x401000),
         path: "/test".to_string(),
         ..Default::default()
     };
@@ -185,7 +185,7 @@ fn test_module_size_calculation() {
             id: 0,
             base,
             end,
-            entry: base,
+            entry: Some(base),
             path: "/test".to_string(),
             ..Default::default()
         };
@@ -204,7 +204,7 @@ fn test_basic_block_absolute_address_calculation() {
         id: 0,
         base: 0x400000,
         end: 0x500000,
-        entry: 0x401000,
+        entry: Some(0x401000),
         path: "/test".to_string(),
         ..Default::default()
     };
@@ -365,7 +365,7 @@ fn test_module_lookup_with_non_sequential_scenarios() {
             id: 0,
             base: 0x400000,
             end: 0x500000,
-            entry: 0x401000,
+            entry: Some(0x401000),
             path: "/custom_entry".to_string(),
             ..Default::default()
         })
@@ -373,7 +373,7 @@ fn test_module_lookup_with_non_sequential_scenarios() {
             id: 1,
             base: 0x600000, // Gap in addresses
             end: 0x500000,  // Inverted range (end < base)
-            entry: 0x650000,
+            entry: Some(0x650000),
             path: "/inverted".to_string(),
             ..Default::default()
         })
@@ -396,3 +396,449 @@ fn test_module_lookup_with_non_sequential_scenarios() {
         "/custom_entry"
     );
 }
+
+#[test]
+fn test_resolved_blocks() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .add_coverage(0, 0x3000, 8)
+        .build()
+        .unwrap();
+
+    let resolved: Vec<_> = coverage.resolved_blocks().collect();
+    assert_eq!(resolved.len(), 3);
+    for (resolved, bb) in resolved.iter().zip(&coverage.basic_blocks) {
+        let module = coverage.find_module(bb.module_id).unwrap();
+        assert_eq!(resolved.absolute_address(), bb.absolute_address(module));
+        assert_eq!(resolved.offset, bb.start);
+        assert_eq!(resolved.size, bb.size);
+        assert_eq!(resolved.module_path(), module.path);
+    }
+
+    // Blocks with an invalid module ID are skipped
+    coverage.basic_blocks.push(BasicBlock {
+        module_id: 5,
+        start: 0x4000,
+        size: 4,
+    });
+    assert_eq!(coverage.resolved_blocks().count(), 3);
+}
+
+#[test]
+fn test_find_overlaps() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/disjoint.so", 0x7fff00000000, 0x7fff00100000)
+        .add_module("/bin/b", 0x480000, 0x580000)
+        .build()
+        .unwrap();
+
+    assert_eq!(coverage.find_overlaps(), vec![(0, 2)]);
+
+    // Adjacent modules do not overlap since `end` is exclusive
+    let adjacent = CoverageData::builder()
+        .add_module("/a", 0x1000, 0x2000)
+        .add_module("/b", 0x2000, 0x3000)
+        .build()
+        .unwrap();
+    assert!(adjacent.find_overlaps().is_empty());
+}
+
+#[test]
+fn test_duplicate_paths() {
+    let coverage = CoverageData::builder()
+        .add_module("/lib/libc.so", 0x7f0000000000, 0x7f0000100000)
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7f1000000000, 0x7f1000100000)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        coverage.duplicate_paths(),
+        vec![("/lib/libc.so".to_string(), vec![0, 2])]
+    );
+    // Advisory only: the data is still valid
+    coverage.validate().unwrap();
+}
+
+#[test]
+fn test_absolute_address_checked() {
+    let module = ModuleEntry {
+        base: u64::MAX,
+        end: u64::MAX,
+        path: "/top".to_string(),
+        ..Default::default()
+    };
+    let bb = BasicBlock {
+        module_id: 0,
+        start: 0x10,
+        size: 4,
+    };
+    assert_eq!(bb.absolute_address_checked(&module), None);
+
+    let at_base = BasicBlock { start: 0, ..bb };
+    assert_eq!(at_base.absolute_address_checked(&module), Some(u64::MAX));
+
+    let normal = ModuleEntry {
+        base: 0x400000,
+        ..module
+    };
+    assert_eq!(bb.absolute_address_checked(&normal), Some(0x400010));
+}
+
+#[test]
+fn test_format_block_with_symbol_resolver() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_coverage(0, 0x1010, 32)
+        .add_coverage(0, 0x2000, 16)
+        .build()
+        .unwrap();
+
+    let resolver = |path: &str, offset: u32| {
+        (path == "/bin/program" && (0x1000..0x1100).contains(&offset))
+            .then(|| format!("main+0x{:x}", offset - 0x1000))
+    };
+
+    let resolved = coverage
+        .format_block(&coverage.basic_blocks[0], &resolver)
+        .unwrap();
+    assert!(resolved.contains("/bin/program"));
+    assert!(resolved.contains("0x401010"));
+    assert!(resolved.ends_with(" -> main+0x10"));
+
+    let unresolved = coverage
+        .format_block(&coverage.basic_blocks[1], &resolver)
+        .unwrap();
+    assert!(!unresolved.contains("->"));
+
+    let plain = coverage
+        .format_block(&coverage.basic_blocks[0], &NoSymbolResolver)
+        .unwrap();
+    assert!(plain.ends_with("/bin/program"));
+
+    let invalid = BasicBlock {
+        module_id: 9,
+        start: 0,
+        size: 1,
+    };
+    assert!(coverage.format_block(&invalid, &NoSymbolResolver).is_none());
+}
+
+#[test]
+fn test_find_module_by_path() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so.6", 0x7fff00000000, 0x7fff00100000)
+        .add_module("/lib/libm.so.6", 0x7ffe00000000, 0x7ffe00100000)
+        .build()
+        .unwrap();
+
+    // Exact match
+    assert_eq!(
+        coverage.find_module_by_path("/lib/libc.so.6").unwrap().id,
+        1
+    );
+    assert!(coverage.find_module_by_path("libc.so.6").is_none());
+    assert!(coverage.find_module_by_path("").is_none());
+
+    // Substring match
+    let libs: Vec<_> = coverage
+        .find_modules_by_path_substring("/lib/")
+        .iter()
+        .map(|m| m.id)
+        .collect();
+    assert_eq!(libs, vec![1, 2]);
+    assert!(coverage
+        .find_modules_by_path_substring("missing")
+        .is_empty());
+
+    // An empty needle matches everything
+    assert_eq!(coverage.find_modules_by_path_substring("").len(), 3);
+}
+
+#[test]
+fn test_blocks_in_address_range() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(0, 0x0ff0, 0x20) // starts before lo, straddles into range
+        .add_coverage(0, 0x1000, 0x10) // starts exactly at lo
+        .add_coverage(0, 0x1ff8, 0x10) // starts inside, straddles hi
+        .add_coverage(0, 0x2000, 0x10) // starts exactly at hi
+        .add_coverage(1, 0x1000, 0x10) // different module
+        .build()
+        .unwrap();
+    coverage.basic_blocks.push(BasicBlock {
+        module_id: 7,
+        start: 0x1000,
+        size: 4,
+    });
+
+    let starts: Vec<_> = coverage
+        .blocks_in_address_range(0x401000, 0x402000)
+        .iter()
+        .map(|bb| (bb.module_id, bb.start))
+        .collect();
+    assert_eq!(starts, vec![(0, 0x1000), (0, 0x1ff8)]);
+
+    assert!(coverage
+        .blocks_in_address_range(0x402000, 0x401000)
+        .is_empty());
+}
+
+#[test]
+fn test_covered_address_bounds() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(1, 0x2000, 16)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(0, 0x1800, 8)
+        .build()
+        .unwrap();
+    assert_eq!(
+        coverage.covered_address_bounds(),
+        Some((0x401000, 0x7fff00002010))
+    );
+
+    let empty = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .build()
+        .unwrap();
+    assert_eq!(empty.covered_address_bounds(), None);
+
+    // Blocks overflowing u64 are skipped rather than panicking
+    let top = CoverageData::builder()
+        .add_module("/top", u64::MAX - 0x10, u64::MAX)
+        .add_coverage(0, 0x8, 0x100)
+        .add_coverage(0, 0x0, 0x4)
+        .build()
+        .unwrap();
+    assert_eq!(
+        top.covered_address_bounds(),
+        Some((u64::MAX - 0x10, u64::MAX - 0xc))
+    );
+}
+
+#[test]
+fn test_covered_block_set() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(0, 0x1000, 8)
+        .add_coverage(1, 0x2000, 16)
+        .build()
+        .unwrap();
+
+    let set = coverage.covered_block_set();
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&(0, 0x1000)));
+    assert!(set.contains(&(1, 0x2000)));
+    assert!(!set.contains(&(1, 0x1000)));
+
+    assert!(coverage.is_block_covered(0, 0x1000));
+    assert!(coverage.is_block_covered(1, 0x2000));
+    assert!(!coverage.is_block_covered(0, 0x2000));
+    assert!(!coverage.is_block_covered(0, 0x1004));
+    assert!(!coverage.is_block_covered(2, 0x1000));
+}
+
+#[test]
+fn test_module_summaries() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/program", 0x400000, 0x400100)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00000400)
+        .add_module("/lib/empty.so", 0x500000, 0x500000)
+        .add_coverage(0, 0x00, 0x20)
+        .add_coverage(0, 0x40, 0x20)
+        .add_coverage(1, 0x100, 0x10)
+        .build()
+        .unwrap();
+
+    let summaries = coverage.module_summaries();
+    assert_eq!(summaries.len(), 3);
+
+    assert_eq!(summaries[0].id, 0);
+    assert_eq!(summaries[0].path, "/bin/program");
+    assert_eq!(summaries[0].size, 0x100);
+    assert_eq!(summaries[0].block_count, 2);
+    assert_eq!(summaries[0].covered_bytes, 0x40);
+    assert_eq!(summaries[0].coverage_ratio, Some(0.25));
+
+    assert_eq!(summaries[1].block_count, 1);
+    assert_eq!(summaries[1].covered_bytes, 0x10);
+    assert_eq!(
+        summaries[1].coverage_ratio,
+        Some(0x10 as f64 / 0x400 as f64)
+    );
+
+    assert_eq!(summaries[2].block_count, 0);
+    assert_eq!(summaries[2].covered_bytes, 0);
+    assert_eq!(summaries[2].coverage_ratio, None);
+}
+
+#[test]
+fn test_module_size_checked() {
+    let normal = ModuleEntry {
+        base: 0x400000,
+        end: 0x500000,
+        ..Default::default()
+    };
+    assert_eq!(normal.size_checked(), Some(0x100000));
+
+    let inverted = ModuleEntry {
+        base: 0x500000,
+        end: 0x400000,
+        ..Default::default()
+    };
+    assert_eq!(inverted.size_checked(), None);
+    assert_eq!(inverted.size(), 0);
+
+    let coverage = CoverageData::builder()
+        .add_full_module(ModuleEntry {
+            path: "/bin/inverted".to_string(),
+            ..inverted
+        })
+        .add_coverage(0, 0x10, 4)
+        .build()
+        .unwrap();
+    assert_eq!(coverage.module_summaries()[0].coverage_ratio, None);
+}
+
+#[test]
+fn test_modules_by_coverage() {
+    let coverage = CoverageData::builder()
+        .add_module("/a", 0x100000, 0x200000)
+        .add_module("/b", 0x200000, 0x300000)
+        .add_module("/c", 0x300000, 0x400000)
+        .add_module("/d", 0x400000, 0x500000)
+        .add_coverage(0, 0x0, 16)
+        .add_coverage(1, 0x0, 64)
+        .add_coverage(2, 0x0, 8)
+        .add_coverage(2, 0x10, 8)
+        .add_coverage(3, 0x0, 64)
+        .build()
+        .unwrap();
+
+    let ids = |modules: Vec<&ModuleEntry>| modules.iter().map(|m| m.id).collect::<Vec<_>>();
+
+    assert_eq!(ids(coverage.modules_by_coverage(true)), vec![1, 3, 0, 2]);
+    assert_eq!(ids(coverage.modules_by_coverage(false)), vec![0, 2, 1, 3]);
+}
+
+#[test]
+fn test_interval_index_lookup() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .add_coverage(0, 0x1000, 0x40) // 0x401000..0x401040
+        .add_coverage(0, 0x1020, 0x10) // 0x401020..0x401030
+        .add_coverage(0, 0x1000, 0x08) // 0x401000..0x401008
+        .add_coverage(1, 0x1020, 0x10) // 0x7f1020..0x7f1030
+        .add_coverage(0, 0x2000, 0) // empty, never matches
+        .build()
+        .unwrap();
+
+    let index = coverage.interval_index();
+    assert_eq!(index.len(), 4);
+
+    let hits = index.lookup(0x401024);
+    assert_eq!(hits.len(), 2);
+    assert!(hits.contains(&&coverage.basic_blocks[0]));
+    assert!(hits.contains(&&coverage.basic_blocks[1]));
+
+    let hits = index.lookup(0x401004);
+    assert_eq!(hits.len(), 2);
+    assert!(hits.contains(&&coverage.basic_blocks[0]));
+    assert!(hits.contains(&&coverage.basic_blocks[2]));
+
+    assert_eq!(index.lookup(0x7f102f), vec![&coverage.basic_blocks[3]]);
+    assert!(index.lookup(0x401040).is_empty());
+    assert!(index.lookup(0x402000).is_empty());
+    assert!(index.lookup(0).is_empty());
+}
+
+#[test]
+fn test_module_for_block_and_absolute_address() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .add_coverage(1, 0x2000, 16)
+        .build()
+        .unwrap();
+
+    let bb = &coverage.basic_blocks[0];
+    assert_eq!(coverage.module_for_block(bb).unwrap().path, "/lib/libc.so");
+    assert_eq!(coverage.absolute_address_of(bb), Some(0x7fff00002000));
+
+    let orphan = BasicBlock {
+        start: 0x10,
+        size: 4,
+        module_id: 5,
+    };
+    assert!(coverage.module_for_block(&orphan).is_none());
+    assert_eq!(coverage.absolute_address_of(&orphan), None);
+
+    let mut near_top = coverage.clone();
+    near_top.modules[1].base = u64::MAX - 0x100;
+    assert_eq!(near_top.absolute_address_of(bb), None);
+}
+
+#[test]
+fn test_module_coverage_bitmap() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/small", 0x1000, 0x1020)
+        .add_module("/bin/huge", 0x0, 0x1_0000_0000)
+        .add_coverage(0, 0x4, 4)
+        .add_coverage(0, 0x10, 2)
+        .add_coverage(0, 0x1e, 8)
+        .build()
+        .unwrap();
+
+    let bitmap = coverage.module_coverage_bitmap(0).unwrap();
+    assert_eq!(bitmap.len(), 0x20);
+    let set: Vec<usize> = (0..bitmap.len()).filter(|&i| bitmap[i]).collect();
+    // The last block is clipped to the module end
+    assert_eq!(set, vec![4, 5, 6, 7, 0x10, 0x11, 0x1e, 0x1f]);
+
+    assert_eq!(coverage.module_coverage_bitmap(1), None);
+    assert_eq!(coverage.module_coverage_bitmap(2), None);
+}
+
+#[test]
+fn test_same_module_layout() {
+    let build = |paths: &[(&str, u64, u64)]| {
+        let mut builder = CoverageData::builder();
+        for &(path, base, end) in paths {
+            builder = builder.add_module(path, base, end);
+        }
+        builder.build().unwrap()
+    };
+    let layout = [
+        ("/bin/a", 0x400000, 0x500000),
+        ("/lib/b.so", 0x7f0000, 0x800000),
+    ];
+    let mut first = build(&layout);
+    let second = build(&layout);
+    first.modules[0].entry = Some(0x401000);
+    first.basic_blocks.push(BasicBlock {
+        start: 0x10,
+        size: 4,
+        module_id: 1,
+    });
+    // Entry points and coverage do not matter
+    assert!(first.same_module_layout(&second));
+
+    let reordered = build(&[layout[1], layout[0]]);
+    assert!(!first.same_module_layout(&reordered));
+
+    let rebased = build(&[layout[0], ("/lib/b.so", 0x7e0000, 0x800000)]);
+    assert!(!first.same_module_layout(&rebased));
+
+    assert!(!first.same_module_layout(&build(&layout[..1])));
+}
@@ -376,3 +376,31 @@ fn test_concurrent_access_patterns() {
     // Should complete quickly
     assert!(duration.as_millis() < 100);
 }
+
+#[test]
+fn test_file_write_performance_many_modules() {
+    let module_count = 1000;
+
+    let mut builder = CoverageData::builder()
+        .flavor("performance_test")
+        .module_version(ModuleTableVersion::V4);
+    for i in 0..module_count {
+        let base = 0x400000 + i * 0x100000;
+        builder = builder.add_module(&format!("/usr/lib/module_{i}.so"), base, base + 0x50000);
+    }
+    let coverage = builder.build().unwrap();
+
+    let temp_file = tempfile::NamedTempFile::new().unwrap();
+    let write_start = Instant::now();
+    drcov::to_file(&coverage, temp_file.path()).unwrap();
+    let write_duration = write_start.elapsed();
+
+    println!("File write performance: {module_count} modules in {write_duration:?}");
+
+    let parsed = drcov::from_file(temp_file.path()).unwrap();
+    assert_eq!(parsed.modules.len(), module_count as usize);
+    assert!(
+        write_duration.as_millis() < 1000,
+        "File write took too long: {write_duration:?}"
+    );
+}
@@ -1,4 +1,7 @@
-use drcov::{from_reader, to_writer, CoverageData, ModuleEntry, ModuleTableVersion};
+use drcov::{
+    from_reader, to_writer, to_writer_with_options, to_writer_with_raw_bb, verify_roundtrip,
+    AddressWidth, CoverageData, Error, ModuleEntry, ModuleTableVersion, WriteOptions,
+};
 use std::io::Cursor;
 
 #[test]
@@ -11,7 +14,7 @@ fn test_multiple_serialization_rounds() {
             id: 0,
             base: 0x400000,
             end: 0x500000,
-            entry: 0x401000,
+            entry: Some(0x401000),
             path: "/bin/test".to_string(),
             containing_id: Some(-1),
             offset: Some(0x1000),
@@ -183,7 +186,7 @@ fn test_large_dataset_consistency() {
             id: i as u32,
             base,
             end: base + 0x50000,
-            entry: base + 0x1000,
+            entry: Some(base + 0x1000),
             path: format!("/lib/module_{i}.so"),
             containing_id: Some(if i % 2 == 0 { -1 } else { (i / 2) as i32 }),
             offset: None, // V3 doesn't support offset
@@ -250,7 +253,7 @@ fn test_format_version_consistency() {
                 id: 0,
                 base: 0x400000,
                 end: 0x500000,
-                entry: 0x401000,
+                entry: Some(0x401000),
                 path: "/bin/format_test".to_string(),
                 containing_id: if version >= ModuleTableVersion::V3 {
                     Some(-1)
@@ -394,6 +397,7 @@ fn test_field_order_independence() {
 }
 
 #[test]
+#[allow(clippy::useless_vec)]
 fn test_empty_data_consistency() {
     // Test consistency with various empty data scenarios
     let empty_cases = vec![
@@ -467,7 +471,7 @@ fn test_deterministic_output() {
             id: 0,
             base: 0x400000,
             end: 0x500000,
-            entry: 0x401000,
+            entry: Some(0x401000),
             path: "/bin/deterministic".to_string(),
             containing_id: Some(-1),
             offset: Some(0x1000),
@@ -492,3 +496,358 @@ fn test_deterministic_output() {
         assert_eq!(&outputs[0], output, "Output {i} differs from output 0");
     }
 }
+
+#[test]
+fn test_force_windows_columns() {
+    let coverage = CoverageData::builder()
+        .module_version(ModuleTableVersion::V4)
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .build()
+        .unwrap();
+
+    let options = WriteOptions {
+        force_windows_columns: true,
+        ..Default::default()
+    };
+    let mut buffer = Vec::new();
+    to_writer_with_options(&coverage, &mut buffer, &options).unwrap();
+    let output = String::from_utf8_lossy(&buffer);
+
+    assert!(output.contains(
+        "Columns: id, containing_id, start, end, entry, offset, checksum, timestamp, path"
+    ));
+    assert!(output.contains("0x00000000, 0x00000000, /bin/test"));
+
+    let parsed = from_reader(Cursor::new(buffer)).unwrap();
+    assert_eq!(parsed.modules[0].checksum, Some(0));
+    assert_eq!(parsed.modules[0].timestamp, Some(0));
+
+    // Without the option, no Windows columns are emitted
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+    assert!(!String::from_utf8_lossy(&buffer).contains("checksum"));
+}
+
+#[test]
+fn test_partial_windows_fields_keep_rows_consistent() {
+    let coverage = CoverageData::builder()
+        .module_version(ModuleTableVersion::V2)
+        .add_full_module(ModuleEntry {
+            id: 0,
+            base: 0x400000,
+            end: 0x500000,
+            path: "/bin/test".to_string(),
+            checksum: Some(0x1234),
+            timestamp: Some(0x5678),
+            ..Default::default()
+        })
+        .add_module("/lib/libc.so", 0x7fff00000000, 0x7fff00100000)
+        .build()
+        .unwrap();
+
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+    let parsed = from_reader(Cursor::new(buffer)).unwrap();
+
+    assert_eq!(parsed.modules[1].path, "/lib/libc.so");
+    assert_eq!(parsed.modules[1].checksum, Some(0));
+}
+
+#[test]
+fn test_minimal_address_width() {
+    let coverage = CoverageData::builder()
+        .module_version(ModuleTableVersion::V2)
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .add_coverage(0, 0x1000, 32)
+        .build()
+        .unwrap();
+
+    let options = WriteOptions {
+        address_width: AddressWidth::Minimal,
+        ..Default::default()
+    };
+    let mut minimal = Vec::new();
+    to_writer_with_options(&coverage, &mut minimal, &options).unwrap();
+    let output = String::from_utf8_lossy(&minimal);
+    assert!(output.contains("0, 0x400000, 0x500000, 0x0, /bin/test"));
+    assert!(!output.contains("0x0000000000400000"));
+
+    let mut padded = Vec::new();
+    to_writer(&coverage, &mut padded).unwrap();
+    assert!(String::from_utf8_lossy(&padded).contains("0x0000000000400000"));
+
+    let from_minimal = from_reader(Cursor::new(minimal)).unwrap();
+    let from_padded = from_reader(Cursor::new(padded)).unwrap();
+    assert_eq!(from_minimal, from_padded);
+    assert_eq!(from_minimal, coverage);
+}
+
+#[test]
+fn test_module_offset_text() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_module("C:\\Windows\\System32\\ntdll.dll", 0x10000000, 0x10100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2a0, 16)
+        .build()
+        .unwrap();
+
+    let mut buffer = Vec::new();
+    coverage.write_module_offset_text(&mut buffer).unwrap();
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "program+0x1000\nntdll.dll+0x2a0\n"
+    );
+}
+
+#[test]
+fn test_line_breaks_rejected() {
+    let mut coverage = CoverageData::builder()
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .build()
+        .unwrap();
+
+    coverage.header.flavor = "bad\nflavor".to_string();
+    let mut buffer = Vec::new();
+    match to_writer(&coverage, &mut buffer) {
+        Err(Error::ValidationError(msg)) => assert_eq!(msg, "flavor must not contain line breaks"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+    assert!(buffer.is_empty());
+
+    coverage.header.flavor = "ok".to_string();
+    coverage.modules[0].path = "/bin/te\rst".to_string();
+    assert!(matches!(
+        to_writer(&coverage, &mut Vec::new()),
+        Err(Error::ValidationError(_))
+    ));
+
+    let builder_result = CoverageData::builder()
+        .add_module("/bin/a\nb", 0x400000, 0x500000)
+        .build();
+    assert!(matches!(builder_result, Err(Error::ValidationError(_))));
+}
+
+#[test]
+fn test_batched_bb_output_is_contiguous() {
+    let mut builder = CoverageData::builder()
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/bin/b", 0x500000, 0x600000);
+    for i in 0..25_000u32 {
+        builder = builder.add_coverage((i % 2) as u16, i * 4, (i % 64) as u16);
+    }
+    let coverage = builder.build().unwrap();
+
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+
+    let mut expected = Vec::new();
+    for bb in &coverage.basic_blocks {
+        expected.extend_from_slice(&bb.start.to_le_bytes());
+        expected.extend_from_slice(&bb.size.to_le_bytes());
+        expected.extend_from_slice(&bb.module_id.to_le_bytes());
+    }
+    assert!(buffer.ends_with(&expected));
+    assert!(
+        String::from_utf8_lossy(&buffer[..buffer.len() - expected.len()])
+            .ends_with("BB Table: 25000 bbs\n")
+    );
+}
+
+#[test]
+fn test_verify_roundtrip_accepts_lossless_data() {
+    let coverage = CoverageData::builder()
+        .module_version(ModuleTableVersion::V4)
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000000000, 0x7f0000100000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .build()
+        .unwrap();
+    verify_roundtrip(&coverage).unwrap();
+}
+
+#[test]
+fn test_verify_roundtrip_reports_legacy_field_loss() {
+    // Legacy module tables have no checksum column, so it is dropped on write.
+    let coverage = CoverageData::builder()
+        .module_version(ModuleTableVersion::Legacy)
+        .add_full_module(ModuleEntry {
+            id: 0,
+            base: 0x400000,
+            end: 0x500000,
+            entry: Some(0x401000),
+            path: "C:\\app.exe".to_string(),
+            checksum: Some(0x1234),
+            ..Default::default()
+        })
+        .add_coverage(0, 0x1000, 32)
+        .build()
+        .unwrap();
+
+    match verify_roundtrip(&coverage) {
+        Err(Error::ValidationError(msg)) => {
+            assert!(msg.contains("module 0 checksum"), "{msg}");
+            assert!(msg.contains("4660"), "{msg}");
+        }
+        other => panic!("expected a checksum mismatch, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_minimal_width_file_roundtrips_byte_for_byte() {
+    let mut original = b"DRCOV VERSION: 2\n\
+DRCOV FLAVOR: drcov-64\n\
+Module Table: version 4, count 2\n\
+Columns: id, containing_id, start, end, entry, offset, checksum, timestamp, path\n\
+0, 0, 0x400000, 0x450000, 0x401000, 0x0, 0x1a2b, 0x5f3e, C:\\app.exe\n\
+1, 1, 0x7ff000000000, 0x7ff000100000, 0x0, 0x0, 0x0, 0x0, C:\\Windows\\ntdll.dll\n\
+BB Table: 2 bbs\n"
+        .to_vec();
+    original.extend_from_slice(&[0x00, 0x10, 0x00, 0x00, 0x20, 0x00, 0x00, 0x00]);
+    original.extend_from_slice(&[0x00, 0x20, 0x00, 0x00, 0x10, 0x00, 0x01, 0x00]);
+
+    let coverage = from_reader(Cursor::new(&original)).unwrap();
+    let options = WriteOptions {
+        address_width: AddressWidth::Minimal,
+        ..Default::default()
+    };
+    let mut rewritten = Vec::new();
+    to_writer_with_options(&coverage, &mut rewritten, &options).unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&rewritten),
+        String::from_utf8_lossy(&original)
+    );
+    assert_eq!(rewritten, original);
+}
+
+#[test]
+fn test_omit_empty_bb_table() {
+    let coverage = CoverageData::builder()
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .build()
+        .unwrap();
+    let options = WriteOptions {
+        omit_empty_bb_table: true,
+        ..Default::default()
+    };
+
+    let mut buffer = Vec::new();
+    to_writer_with_options(&coverage, &mut buffer, &options).unwrap();
+    let output = String::from_utf8(buffer.clone()).unwrap();
+    assert!(!output.contains("BB Table"));
+    assert!(output.ends_with("/bin/test\n"));
+
+    let parsed = from_reader(Cursor::new(buffer)).unwrap();
+    assert_eq!(parsed.modules, coverage.modules);
+    assert!(parsed.basic_blocks.is_empty());
+
+    // The line is still written when there are blocks, and by default
+    let mut default_output = Vec::new();
+    to_writer(&coverage, &mut default_output).unwrap();
+    assert!(String::from_utf8_lossy(&default_output).ends_with("BB Table: 0 bbs\n"));
+
+    let with_blocks = CoverageData::builder()
+        .add_module("/bin/test", 0x400000, 0x500000)
+        .add_coverage(0, 0x1000, 32)
+        .build()
+        .unwrap();
+    let mut buffer = Vec::new();
+    to_writer_with_options(&with_blocks, &mut buffer, &options).unwrap();
+    assert!(String::from_utf8_lossy(&buffer).contains("BB Table: 1 bbs\n"));
+}
+
+#[test]
+fn test_flavor_surrounding_spaces_roundtrip() {
+    for flavor in ["  leading", "trailing  ", "  both  ", " "] {
+        let coverage = CoverageData::builder()
+            .flavor(flavor)
+            .add_module("/bin/test", 0x400000, 0x500000)
+            .add_coverage(0, 0x1000, 16)
+            .build()
+            .unwrap();
+
+        let mut buffer = Vec::new();
+        to_writer(&coverage, &mut buffer).unwrap();
+        let text = String::from_utf8_lossy(&buffer);
+        assert!(text.contains(&format!("DRCOV FLAVOR: {flavor}\n")));
+
+        let parsed = from_reader(Cursor::new(&buffer)).unwrap();
+        assert_eq!(parsed.header.flavor, flavor);
+        verify_roundtrip(&coverage).unwrap();
+    }
+}
+
+#[test]
+fn test_dynamorio_text_dump() {
+    let coverage = CoverageData::builder()
+        .flavor("drcov")
+        .module_version(ModuleTableVersion::V2)
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2a0, 4)
+        .build()
+        .unwrap();
+
+    let mut buffer = Vec::new();
+    coverage.write_dynamorio_text(&mut buffer).unwrap();
+    let text = String::from_utf8(buffer).unwrap();
+
+    let mut binary = Vec::new();
+    to_writer(&coverage, &mut binary).unwrap();
+    let header_len = binary.len() - "BB Table: 2 bbs\n".len() - 16;
+    let (header, bb_section) = text.split_at(header_len);
+    assert_eq!(header.as_bytes(), &binary[..header_len]);
+    assert_eq!(
+        bb_section,
+        "BB Table: 2 bbs\n\
+         module id, start, size:\n\
+         module[  0]: 0x0000000000001000,  32\n\
+         module[  1]: 0x00000000000002a0,   4\n"
+    );
+}
+
+#[test]
+fn test_raw_bb_bytes_roundtrip() {
+    let coverage = CoverageData::builder()
+        .flavor("raw")
+        .add_module("/bin/a", 0x400000, 0x500000)
+        .add_module("/lib/b.so", 0x7f0000, 0x800000)
+        .add_coverage(0, 0x1000, 32)
+        .add_coverage(1, 0x2000, 16)
+        .add_coverage(0, 0x3000, 8)
+        .build()
+        .unwrap();
+    let raw = coverage.basic_blocks_to_bytes();
+
+    let mut rebuilt = coverage.clone();
+    rebuilt.clear_coverage();
+    rebuilt.append_raw_bb_bytes(&raw).unwrap();
+    assert_eq!(rebuilt, coverage);
+
+    let mut modules_only = coverage.clone();
+    modules_only.clear_coverage();
+    let mut fast = Vec::new();
+    to_writer_with_raw_bb(&modules_only, &mut fast, &raw).unwrap();
+    let mut regular = Vec::new();
+    to_writer(&coverage, &mut regular).unwrap();
+    assert_eq!(fast, regular);
+    assert_eq!(from_reader(Cursor::new(&fast)).unwrap(), coverage);
+
+    // Partial records and unknown modules are rejected without side effects
+    assert!(matches!(
+        rebuilt.append_raw_bb_bytes(&raw[..12]),
+        Err(Error::ValidationError(_))
+    ));
+    let mut bad_module = raw.clone();
+    bad_module[6] = 9;
+    assert!(matches!(
+        rebuilt.append_raw_bb_bytes(&bad_module),
+        Err(Error::ValidationError(_))
+    ));
+    assert_eq!(rebuilt.basic_blocks.len(), 3);
+    let mut out = Vec::new();
+    assert!(to_writer_with_raw_bb(&modules_only, &mut out, &bad_module).is_err());
+    assert!(out.is_empty());
+}
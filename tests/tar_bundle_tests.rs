@@ -0,0 +1,66 @@
+#![cfg(all(feature = "tar", feature = "flate2"))]
+
+use drcov::{from_tar_gz, from_tar_gz_lenient, to_writer, CoverageData, Error};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+fn drcov_bytes(flavor: &str) -> Vec<u8> {
+    let coverage = CoverageData::builder()
+        .flavor(flavor)
+        .add_module("/bin/program", 0x400000, 0x500000)
+        .add_coverage(0, 0x1000, 32)
+        .build()
+        .unwrap();
+    let mut buffer = Vec::new();
+    to_writer(&coverage, &mut buffer).unwrap();
+    buffer
+}
+
+fn build_tar_gz(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, *data).unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+#[test]
+fn test_from_tar_gz_reads_drcov_entries() {
+    let first = drcov_bytes("first");
+    let second = drcov_bytes("second");
+    let bundle = build_tar_gz(&[
+        ("run1/a.drcov", &first),
+        ("README.txt", b"not coverage"),
+        ("run2/b.drcov", &second),
+    ]);
+
+    let parsed = from_tar_gz(bundle.as_slice()).unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[0].0, "run1/a.drcov");
+    assert_eq!(parsed[0].1.header.flavor, "first");
+    assert_eq!(parsed[1].0, "run2/b.drcov");
+    assert_eq!(parsed[1].1.header.flavor, "second");
+    assert_eq!(parsed[1].1.basic_blocks.len(), 1);
+}
+
+#[test]
+fn test_from_tar_gz_entry_errors() {
+    let good = drcov_bytes("good");
+    let bundle = build_tar_gz(&[("bad.drcov", b"garbage\n"), ("good.drcov", &good)]);
+
+    assert!(matches!(
+        from_tar_gz(bundle.as_slice()),
+        Err(Error::InvalidFormat(_))
+    ));
+
+    let results = from_tar_gz_lenient(bundle.as_slice()).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0, "bad.drcov");
+    assert!(results[0].1.is_err());
+    assert_eq!(results[1].0, "good.drcov");
+    assert_eq!(results[1].1.as_ref().unwrap().header.flavor, "good");
+}